@@ -0,0 +1,62 @@
+use anyhow::{Context, Result};
+use base64::Engine;
+use std::io::Write;
+
+/// Copy text to the clipboard, with the configured command if there is one, otherwise
+/// with the OSC 52 escape sequence, which the terminal handles (and works over SSH)
+pub fn copy(text: &str, command: Option<&[String]>) -> Result<()> {
+    match command.filter(|cmd| !cmd.is_empty()) {
+        Some(cmd) => copy_with_command(text, cmd),
+        None => {
+            let mut stdout = std::io::stdout();
+            stdout
+                .write_all(osc52_sequence(text, std::env::var_os("TMUX").is_some()).as_bytes())?;
+            stdout.flush()?;
+            Ok(())
+        }
+    }
+}
+
+/// The OSC 52 sequence that sets the clipboard to the text.
+/// Inside tmux it is wrapped in a passthrough sequence so it reaches the outer terminal.
+pub fn osc52_sequence(text: &str, tmux: bool) -> String {
+    let sequence = format!(
+        "\x1b]52;c;{}\x07",
+        base64::engine::general_purpose::STANDARD.encode(text)
+    );
+    terminal_sequence(sequence, tmux)
+}
+
+/// Inside tmux, wrap an escape sequence in a passthrough sequence so it reaches the
+/// outer terminal
+pub fn terminal_sequence(sequence: String, tmux: bool) -> String {
+    if tmux {
+        format!("\x1bPtmux;\x1b{}\x1b\\", sequence)
+    } else {
+        sequence
+    }
+}
+
+/// Pipe the text to a clipboard command, such as pbcopy, wl-copy or xclip
+fn copy_with_command(text: &str, cmd: &[String]) -> Result<()> {
+    let mut child = std::process::Command::new(&cmd[0])
+        .args(&cmd[1..])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", cmd[0]))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "{} exited with status: {}",
+            cmd[0],
+            status.code().unwrap_or(-1)
+        ))
+    }
+}
@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
 use etcetera::{BaseStrategy, choose_base_strategy};
+use pueue_lib::task::{TaskResult, TaskStatus};
 use serde::Deserialize;
 use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
@@ -8,6 +9,44 @@ use std::path::{Path, PathBuf};
 pub struct Config {
     #[serde(default)]
     pub custom_commands: BTreeMap<String, CustomCommand>,
+    /// Only fetch the last N lines of a finished task's output (default: all of it)
+    #[serde(default)]
+    pub log_lines: Option<usize>,
+    /// Maximum number of lines kept in memory by the log viewer, older lines are dropped
+    #[serde(default)]
+    pub log_max_lines: Option<usize>,
+    /// Command to open logs with, the log file is passed as the last argument
+    /// (default: $PAGER, then $EDITOR, then less)
+    #[serde(default)]
+    pub log_viewer: Option<Vec<String>>,
+    /// Rules for highlighting log lines (default: errors, warnings, panics and tracebacks).
+    /// An empty list turns highlighting off.
+    #[serde(default)]
+    pub highlights: Option<Vec<HighlightRule>>,
+    /// Command to copy with, which is passed the text on stdin (e.g., ["pbcopy"]).
+    /// By default the terminal is asked to copy with the OSC 52 escape sequence,
+    /// this is for terminals that don't support it.
+    #[serde(default)]
+    pub clipboard_command: Option<Vec<String>>,
+    /// Pueue config file of the daemon to connect to (default: pueue's own default location)
+    #[serde(default)]
+    pub pueue_config: Option<PathBuf>,
+    /// Profile in the pueue config file to connect with
+    #[serde(default)]
+    pub pueue_profile: Option<String>,
+    /// Daemons to monitor together, instead of the one given by `pueue_config` and `pueue_profile`
+    #[serde(default)]
+    pub daemons: Vec<DaemonConfig>,
+    /// Notify when tasks finish (default: off)
+    #[serde(default)]
+    pub notifications: Option<NotificationConfig>,
+    /// File the finished runs of tasks are kept in
+    /// (default: pui/history.jsonl in the platform's data directory)
+    #[serde(default)]
+    pub history_file: Option<PathBuf>,
+    /// Commands to run when tasks change status
+    #[serde(default)]
+    pub hooks: Vec<HookConfig>,
     /// Path the config was loaded from (for error messages)
     #[serde(skip)]
     pub config_path: Option<PathBuf>,
@@ -21,6 +60,145 @@ pub struct CustomCommand {
     pub cmd: Vec<String>,
 }
 
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+pub struct DaemonConfig {
+    /// Name shown in the host column and the footer
+    pub name: String,
+    /// Pueue config file of the daemon (default: pueue's default config)
+    #[serde(default)]
+    pub pueue_config: Option<PathBuf>,
+    /// Profile in the pueue config file
+    #[serde(default)]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct NotificationConfig {
+    #[serde(default)]
+    pub method: NotifyMethod,
+    /// Notifier command for the `command` method, passed the title and the message as its
+    /// last two arguments (e.g., ["notify-send"])
+    #[serde(default)]
+    pub command: Option<Vec<String>>,
+    /// Only notify for tasks in these groups (default: all groups)
+    #[serde(default)]
+    pub groups: Vec<String>,
+    /// Only notify for tasks with these labels (default: tasks with or without a label)
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// Only notify for these results (default: all results)
+    #[serde(default)]
+    pub results: Vec<ResultFilter>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifyMethod {
+    /// Ring the terminal bell
+    #[default]
+    Bell,
+    /// OSC 9 escape sequence (iTerm2, WezTerm, kitty, Windows Terminal)
+    Osc9,
+    /// OSC 777 escape sequence (foot, Ghostty, VTE based terminals)
+    Osc777,
+    /// Run `command`
+    Command,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultFilter {
+    Success,
+    /// Any result other than success
+    Failure,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+pub struct HookConfig {
+    /// Status the task changes to
+    pub on: StatusMatcher,
+    /// Status the task changes from (default: any)
+    #[serde(default)]
+    pub from: Option<StatusMatcher>,
+    /// Only for tasks in these groups (default: all groups)
+    #[serde(default)]
+    pub groups: Vec<String>,
+    /// Only for tasks with these labels (default: tasks with or without a label)
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// Only for tasks whose command matches this regular expression
+    #[serde(default)]
+    pub command: Option<String>,
+    /// The command to run and its arguments, where `{id}`, `{group}`, `{label}`, `{status}`,
+    /// `{command}` and `{path}` are replaced by the task's. The task is also passed as JSON
+    /// on stdin.
+    pub run: Vec<String>,
+}
+
+/// A task status to match in hooks
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusMatcher {
+    Stashed,
+    Queued,
+    Running,
+    Paused,
+    /// Finished with any result
+    Done,
+    Success,
+    /// Finished with any result other than success
+    Failure,
+}
+
+impl StatusMatcher {
+    pub fn matches(&self, status: &TaskStatus) -> bool {
+        match (self, status) {
+            (StatusMatcher::Stashed, TaskStatus::Stashed { .. })
+            | (StatusMatcher::Queued, TaskStatus::Queued { .. })
+            | (StatusMatcher::Running, TaskStatus::Running { .. })
+            | (StatusMatcher::Paused, TaskStatus::Paused { .. })
+            | (StatusMatcher::Done, TaskStatus::Done { .. }) => true,
+            (StatusMatcher::Success, TaskStatus::Done { result, .. }) => {
+                matches!(result, TaskResult::Success)
+            }
+            (StatusMatcher::Failure, TaskStatus::Done { result, .. }) => {
+                !matches!(result, TaskResult::Success)
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+pub struct HighlightRule {
+    /// Regular expression matched against each log line (e.g., "(?i)\\berror\\b")
+    pub pattern: String,
+    /// Colour of the matches (e.g., "red", "light yellow" or "#ff8800")
+    pub color: String,
+    #[serde(default)]
+    pub bold: bool,
+    /// Lines matching this rule are errors, which `E` jumps to
+    #[serde(default)]
+    pub error: bool,
+}
+
+impl HighlightRule {
+    fn new(pattern: &str, color: &str, error: bool) -> Self {
+        HighlightRule {
+            pattern: pattern.to_string(),
+            color: color.to_string(),
+            bold: error,
+            error,
+        }
+    }
+}
+
+/// Keys of the task list, which come before any custom command bound to them
+const BUILTIN_KEYS: &[&str] = &[
+    "q", "f", "s", "r", "p", "x", "c", "C", "d", "D", "e", "E", "H", "L", "y", "Y", "m", "w", "j",
+    "k", "?", "+", "=", "-", ">", "ctrl+a",
+];
+
 /// Parsed key binding with optional modifiers
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ParsedKey {
@@ -30,10 +208,10 @@ pub struct ParsedKey {
 }
 
 impl ParsedKey {
-    /// Parse a key string like "g", "ctrl+g", "alt+r", "opt+q"
+    /// Parse a key string like "g", "G", "ctrl+g", "alt+r", "opt+q".
+    /// The modifier's case doesn't matter, the key's does.
     pub fn parse(s: &str) -> Option<Self> {
-        let s = s.trim().to_lowercase();
-        let parts: Vec<&str> = s.split('+').collect();
+        let parts: Vec<&str> = s.trim().split('+').collect();
 
         match parts.as_slice() {
             [key] if key.len() == 1 => Some(ParsedKey {
@@ -43,7 +221,7 @@ impl ParsedKey {
             }),
             [modifier, key] if key.len() == 1 => {
                 let key_char = key.chars().next()?;
-                match *modifier {
+                match modifier.to_lowercase().as_str() {
                     "ctrl" => Some(ParsedKey {
                         key: key_char,
                         ctrl: true,
@@ -66,7 +244,7 @@ impl ParsedKey {
         use crossterm::event::{KeyCode, KeyModifiers};
 
         let code_matches = match key_event.code {
-            KeyCode::Char(c) => c.to_ascii_lowercase() == self.key,
+            KeyCode::Char(c) => c == self.key,
             _ => false,
         };
 
@@ -89,6 +267,66 @@ impl Config {
         Self::load_from_path(&config_path)
     }
 
+    /// Warnings about custom commands bound to a key of the task list, which they'd never run on
+    pub fn key_conflicts(&self) -> Vec<String> {
+        let builtin: Vec<ParsedKey> = BUILTIN_KEYS
+            .iter()
+            .filter_map(|key| ParsedKey::parse(key))
+            .collect();
+        self.custom_commands
+            .iter()
+            .filter(|(_, cmd)| ParsedKey::parse(&cmd.key).is_some_and(|key| builtin.contains(&key)))
+            .map(|(name, cmd)| {
+                format!(
+                    "Custom command '{}' is bound to {:?}, which is already a key of pui",
+                    name, cmd.key
+                )
+            })
+            .collect()
+    }
+
+    /// File to keep the run history in
+    pub fn history_path(&self) -> Result<PathBuf> {
+        match &self.history_file {
+            Some(path) => Ok(path.clone()),
+            None => Ok(choose_base_strategy()?
+                .data_dir()
+                .join("pui")
+                .join("history.jsonl")),
+        }
+    }
+
+    /// Command to open a log file with, without the file argument
+    pub fn log_viewer_command(&self) -> Vec<String> {
+        if let Some(cmd) = self.log_viewer.as_ref().filter(|cmd| !cmd.is_empty()) {
+            return cmd.clone();
+        }
+        ["PAGER", "EDITOR"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .map(|value| {
+                value
+                    .split_whitespace()
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            })
+            .find(|cmd| !cmd.is_empty())
+            .unwrap_or_else(|| vec!["less".to_string()])
+    }
+
+    /// Highlight rules for logs, the defaults unless configured
+    pub fn highlight_rules(&self) -> Vec<HighlightRule> {
+        self.highlights.clone().unwrap_or_else(|| {
+            vec![
+                HighlightRule::new(r"(?i)\berror\b", "red", true),
+                HighlightRule::new(r"panicked at", "red", true),
+                HighlightRule::new(r"^Traceback \(most recent call last\)", "red", true),
+                HighlightRule::new(r"\bFAILED\b", "red", true),
+                HighlightRule::new(r"(?i)\bwarn(ing)?\b", "yellow", false),
+            ]
+        })
+    }
+
     /// Load config from a specific path (useful for testing)
     pub fn load_from_path(path: &Path) -> Result<Self> {
         if path.exists() {
@@ -97,6 +335,22 @@ impl Config {
             let mut config: Config = toml::from_str(&content)
                 .with_context(|| format!("Failed to parse config file {}", path.display()))?;
             config.config_path = Some(path.to_path_buf());
+            crate::log::compile_highlights(&config.highlight_rules())
+                .with_context(|| format!("Invalid highlights in config file {}", path.display()))?;
+            crate::hooks::compile_hooks(&config.hooks)
+                .with_context(|| format!("Invalid hooks in config file {}", path.display()))?;
+            if let Some(notifications) = &config.notifications
+                && notifications.method == NotifyMethod::Command
+                && notifications
+                    .command
+                    .as_ref()
+                    .is_none_or(|cmd| cmd.is_empty())
+            {
+                anyhow::bail!(
+                    "Notification method \"command\" needs a command, in config file {}",
+                    path.display()
+                );
+            }
             Ok(config)
         } else {
             Ok(Config {
@@ -141,6 +395,167 @@ editor = { key = "ctrl+e", cmd = ["nvim", "."] }
         let toml = "";
         let config: Config = toml::from_str(toml).unwrap();
         assert!(config.custom_commands.is_empty());
+        assert_eq!(config.log_lines, None);
+    }
+
+    #[test]
+    fn test_config_parse_log_lines() {
+        let toml = "log_lines = 500";
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.log_lines, Some(500));
+    }
+
+    #[test]
+    fn test_config_log_viewer() {
+        let toml = r#"log_viewer = ["bat", "--paging=always"]"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.log_viewer_command(), vec!["bat", "--paging=always"]);
+    }
+
+    #[test]
+    fn test_config_highlights() {
+        assert!(!Config::default().highlight_rules().is_empty());
+
+        let toml = r#"
+[[highlights]]
+pattern = "TODO"
+color = "light blue"
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(
+            config.highlight_rules(),
+            vec![HighlightRule {
+                pattern: "TODO".to_string(),
+                color: "light blue".to_string(),
+                bold: false,
+                error: false,
+            }]
+        );
+
+        let config: Config = toml::from_str("highlights = []").unwrap();
+        assert!(config.highlight_rules().is_empty());
+    }
+
+    #[test]
+    fn test_config_invalid_highlight_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[[highlights]]\npattern = \"(\"\ncolor = \"red\"\n").unwrap();
+        let err = Config::load_from_path(&path).unwrap_err();
+        assert!(err.to_string().starts_with("Invalid highlights"), "{}", err);
+
+        std::fs::write(
+            &path,
+            "[[highlights]]\npattern = \"x\"\ncolor = \"reddish\"\n",
+        )
+        .unwrap();
+        assert!(Config::load_from_path(&path).is_err());
+    }
+
+    #[test]
+    fn test_config_parse_notifications() {
+        let toml = r#"
+[notifications]
+method = "osc777"
+groups = ["gpu"]
+results = ["failure"]
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let notifications = config.notifications.unwrap();
+        assert_eq!(notifications.method, NotifyMethod::Osc777);
+        assert_eq!(notifications.groups, vec!["gpu"]);
+        assert!(notifications.labels.is_empty());
+        assert_eq!(notifications.results, vec![ResultFilter::Failure]);
+
+        let config: Config = toml::from_str("[notifications]\n").unwrap();
+        assert_eq!(config.notifications.unwrap().method, NotifyMethod::Bell);
+        assert!(Config::default().notifications.is_none());
+    }
+
+    #[test]
+    fn test_config_notification_command_is_required() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[notifications]\nmethod = \"command\"\n").unwrap();
+        assert!(Config::load_from_path(&path).is_err());
+
+        std::fs::write(
+            &path,
+            "[notifications]\nmethod = \"command\"\ncommand = [\"notify-send\"]\n",
+        )
+        .unwrap();
+        assert!(Config::load_from_path(&path).is_ok());
+    }
+
+    #[test]
+    fn test_config_parse_hooks() {
+        let toml = r#"
+[[hooks]]
+on = "failure"
+groups = ["deploy"]
+run = ["./rollback.sh", "{id}"]
+
+[[hooks]]
+on = "success"
+from = "running"
+command = "^cargo "
+run = ["./report.sh"]
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.hooks.len(), 2);
+        assert_eq!(config.hooks[0].on, StatusMatcher::Failure);
+        assert_eq!(config.hooks[0].from, None);
+        assert_eq!(config.hooks[0].groups, vec!["deploy"]);
+        assert_eq!(config.hooks[0].run, vec!["./rollback.sh", "{id}"]);
+        assert_eq!(config.hooks[1].from, Some(StatusMatcher::Running));
+        assert_eq!(config.hooks[1].command.as_deref(), Some("^cargo "));
+    }
+
+    #[test]
+    fn test_config_invalid_hook_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "[[hooks]]\non = \"done\"\ncommand = \"(\"\nrun = [\"x\"]\n",
+        )
+        .unwrap();
+        let err = Config::load_from_path(&path).unwrap_err();
+        assert!(err.to_string().starts_with("Invalid hooks"), "{}", err);
+
+        std::fs::write(&path, "[[hooks]]\non = \"done\"\nrun = []\n").unwrap();
+        assert!(Config::load_from_path(&path).is_err());
+    }
+
+    #[test]
+    fn test_config_parse_pueue_target() {
+        let toml = r#"
+pueue_config = "/srv/pueue/pueue.yml"
+pueue_profile = "work"
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(
+            config.pueue_config,
+            Some(PathBuf::from("/srv/pueue/pueue.yml"))
+        );
+        assert_eq!(config.pueue_profile.as_deref(), Some("work"));
+    }
+
+    #[test]
+    fn test_config_parse_daemons() {
+        let toml = r#"
+[[daemons]]
+name = "workstation"
+
+[[daemons]]
+name = "build"
+pueue_config = "/etc/pueue/build.yml"
+profile = "tls"
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.daemons.len(), 2);
+        assert_eq!(config.daemons[0].pueue_config, None);
+        assert_eq!(config.daemons[1].profile.as_deref(), Some("tls"));
     }
 
     #[test]
@@ -212,10 +627,35 @@ editor = { key = "ctrl+e", cmd = ["nvim", "."] }
     }
 
     #[test]
-    fn test_parsed_key_case_insensitive() {
-        let pk = ParsedKey::parse("CTRL+P").unwrap();
+    fn test_parsed_key_case() {
+        let pk = ParsedKey::parse("CTRL+p").unwrap();
         assert_eq!(pk.key, 'p');
         assert!(pk.ctrl);
+
+        // The key itself is case sensitive
+        let pk = ParsedKey::parse("G").unwrap();
+        assert!(pk.matches(&KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT)));
+        assert!(!pk.matches(&KeyEvent::new(KeyCode::Char('g'), KeyModifiers::NONE)));
+        let pk = ParsedKey::parse("e").unwrap();
+        assert!(!pk.matches(&KeyEvent::new(KeyCode::Char('E'), KeyModifiers::SHIFT)));
+    }
+
+    #[test]
+    fn test_config_key_conflicts() {
+        let toml = r#"
+[custom_commands]
+lazygit = { key = "g", cmd = ["lazygit"] }
+events = { key = "E", cmd = ["true"] }
+quit = { key = "q", cmd = ["true"] }
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(
+            config.key_conflicts(),
+            vec![
+                "Custom command 'events' is bound to \"E\", which is already a key of pui",
+                "Custom command 'quit' is bound to \"q\", which is already a key of pui",
+            ]
+        );
     }
 
     #[test]
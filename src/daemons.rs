@@ -0,0 +1,445 @@
+use crate::pueue_client::{DaemonTarget, PueueClient, PueueClientOps, TaskLog};
+use anyhow::{Result, anyhow};
+use pueue_lib::message::{AddRequest, TaskToRestart};
+use pueue_lib::state::State;
+use std::collections::BTreeMap;
+
+/// Task ids of each daemon are offset by this much in the merged state so they don't clash,
+/// when there are several daemons. The first daemon's ids, and those of a lone daemon, are
+/// unchanged.
+pub const ID_STRIDE: usize = 1_000_000;
+
+/// Index of the daemon a task id (in the merged state of `daemon_count` daemons) belongs to
+pub fn daemon_index(id: usize, daemon_count: usize) -> usize {
+    if daemon_count > 1 { id / ID_STRIDE } else { 0 }
+}
+
+/// Task id on its own daemon
+pub fn local_id(id: usize, daemon_count: usize) -> usize {
+    if daemon_count > 1 { id % ID_STRIDE } else { id }
+}
+
+fn global_id(daemon: usize, id: usize) -> usize {
+    daemon * ID_STRIDE + id
+}
+
+/// Whether a group given by name, eg: in the config or on the command line, is a task's group.
+/// With several daemons, groups are named `daemon/group` and the name can leave out the daemon.
+pub fn group_matches(name: &str, group: &str) -> bool {
+    group == name
+        || group
+            .split_once('/')
+            .is_some_and(|(_, local)| local == name)
+}
+
+/// The result of a request sent to every daemon, which carries on past the daemons that
+/// fail so one that's down doesn't hold up the others
+fn all_succeeded(errors: Vec<String>) -> Result<()> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!("{}", errors.join("; ")))
+    }
+}
+
+/// Name of a daemon and the error of its last request, if it failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DaemonStatus {
+    pub name: String,
+    pub error: Option<String>,
+}
+
+struct Daemon<P> {
+    name: String,
+    /// None on a connection for one task (see `PueueClientOps::new`) to the other daemons,
+    /// or if it couldn't be opened
+    client: Option<P>,
+    error: Option<String>,
+}
+
+/// Clients of several pueue daemons, seen as one.
+/// Their states are merged, with task ids namespaced by daemon (see [`ID_STRIDE`]),
+/// and requests are routed to the daemon of the tasks involved.
+pub struct Daemons<P> {
+    daemons: Vec<Daemon<P>>,
+    /// Daemon of the task whose log is being streamed
+    streaming: Option<usize>,
+}
+
+impl Daemons<PueueClient> {
+    /// Connect to each daemon. Daemons that can't be reached are kept, to be retried on
+    /// `reconnect`, unless none can be reached.
+    pub async fn connect(targets: Vec<(String, DaemonTarget)>) -> Result<Self> {
+        let mut clients = Vec::new();
+        let mut errors = Vec::new();
+        let mut first_error = None;
+        for (name, target) in targets {
+            match PueueClient::new(target.clone()).await {
+                Ok(client) => {
+                    clients.push((name, client));
+                    errors.push(None);
+                }
+                Err(e) => {
+                    clients.push((name, PueueClient::offline(target)));
+                    errors.push(Some(e.to_string()));
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        if let Some(e) = first_error
+            && errors.iter().all(Option::is_some)
+        {
+            return Err(e);
+        }
+        let mut daemons = Self::new(clients);
+        for (daemon, error) in daemons.daemons.iter_mut().zip(errors) {
+            daemon.error = error;
+        }
+        Ok(daemons)
+    }
+
+    /// The daemon connected to, for the title bar, when there's only one
+    pub fn description(&self) -> Option<String> {
+        match &self.daemons[..] {
+            [daemon] => daemon.client.as_ref()?.description(),
+            _ => None,
+        }
+    }
+}
+
+impl<P: PueueClientOps> Daemons<P> {
+    pub fn new(clients: Vec<(String, P)>) -> Self {
+        Self {
+            daemons: clients
+                .into_iter()
+                .map(|(name, client)| Daemon {
+                    name,
+                    client: Some(client),
+                    error: None,
+                })
+                .collect(),
+            streaming: None,
+        }
+    }
+
+    /// Name of a group in the merged state, where each daemon's groups are kept apart by
+    /// naming them after the daemon, as every daemon has a "default" group
+    fn merged_group(&self, daemon: usize, group: &str) -> String {
+        match &self.daemons[..] {
+            [_] => group.to_string(),
+            daemons => format!("{}/{}", daemons[daemon].name, group),
+        }
+    }
+
+    /// Name of a group of the merged state on its daemon, None if it's another daemon's
+    fn local_group(&self, daemon: usize, group: &str) -> Option<String> {
+        match &self.daemons[..] {
+            [_] => Some(group.to_string()),
+            daemons => group
+                .strip_prefix(daemons.get(daemon)?.name.as_str())
+                .and_then(|group| group.strip_prefix('/'))
+                .map(str::to_string),
+        }
+    }
+
+    fn daemon_index(&self, id: usize) -> usize {
+        daemon_index(id, self.daemons.len())
+    }
+
+    fn local_id(&self, id: usize) -> usize {
+        local_id(id, self.daemons.len())
+    }
+
+    /// Group task ids (in the merged state) by daemon, as ids on that daemon
+    fn split_ids(&self, ids: Vec<usize>) -> BTreeMap<usize, Vec<usize>> {
+        let mut by_daemon: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for id in ids {
+            by_daemon
+                .entry(self.daemon_index(id))
+                .or_default()
+                .push(self.local_id(id));
+        }
+        by_daemon
+    }
+
+    fn client(&mut self, daemon: usize) -> Result<&mut P> {
+        let daemon = self
+            .daemons
+            .get_mut(daemon)
+            .ok_or_else(|| anyhow!("Unknown daemon {}", daemon))?;
+        daemon
+            .client
+            .as_mut()
+            .ok_or_else(|| anyhow!("Not connected to {}", daemon.name))
+    }
+}
+
+impl<P: PueueClientOps> PueueClientOps for Daemons<P> {
+    fn new(&self, task_id: usize) -> impl Future<Output = Result<Self>> + 'static {
+        let index = self.daemon_index(task_id);
+        let names: Vec<String> = self.daemons.iter().map(|d| d.name.clone()).collect();
+        let connection = self
+            .daemons
+            .get(index)
+            .and_then(|daemon| daemon.client.as_ref())
+            .map(|client| client.new(self.local_id(task_id)));
+        async move {
+            let mut client = match connection {
+                Some(connection) => Some(connection.await?),
+                None => return Err(anyhow!("Unknown daemon {}", index)),
+            };
+            let daemons = names
+                .into_iter()
+                .enumerate()
+                .map(|(i, name)| Daemon {
+                    name,
+                    client: if i == index { client.take() } else { None },
+                    error: None,
+                })
+                .collect();
+            Ok(Self {
+                daemons,
+                streaming: None,
+            })
+        }
+    }
+
+    async fn get_state(&mut self) -> Result<State> {
+        let mut merged = State {
+            tasks: BTreeMap::new(),
+            groups: BTreeMap::new(),
+        };
+        let mut first_error = None;
+        let mut states = Vec::new();
+        for (index, daemon) in self.daemons.iter_mut().enumerate() {
+            let Some(client) = &mut daemon.client else {
+                continue;
+            };
+            let state = match client.get_state().await {
+                Ok(state) => state,
+                // A daemon that restarted or came up since the last request gets a new connection
+                Err(_) if daemon.error.is_some() && client.reconnect().await.is_ok() => {
+                    match client.get_state().await {
+                        Ok(state) => state,
+                        Err(e) => {
+                            daemon.error = Some(e.to_string());
+                            first_error.get_or_insert(e);
+                            continue;
+                        }
+                    }
+                }
+                Err(e) => {
+                    daemon.error = Some(e.to_string());
+                    first_error.get_or_insert(e);
+                    continue;
+                }
+            };
+            daemon.error = None;
+            states.push((index, state));
+        }
+        for (index, state) in states {
+            for (id, mut task) in state.tasks {
+                task.id = global_id(index, id);
+                task.dependencies = task
+                    .dependencies
+                    .iter()
+                    .map(|id| global_id(index, *id))
+                    .collect();
+                task.group = self.merged_group(index, &task.group);
+                merged.tasks.insert(task.id, task);
+            }
+            for (name, group) in state.groups {
+                merged.groups.insert(self.merged_group(index, &name), group);
+            }
+        }
+        match first_error {
+            Some(e) if self.daemons.iter().all(|daemon| daemon.error.is_some()) => Err(e),
+            _ => Ok(merged),
+        }
+    }
+
+    async fn start_tasks(&mut self, ids: Vec<usize>) -> Result<()> {
+        for (daemon, ids) in self.split_ids(ids) {
+            self.client(daemon)?.start_tasks(ids).await?;
+        }
+        Ok(())
+    }
+
+    async fn restart_tasks(&mut self, tasks: Vec<TaskToRestart>) -> Result<()> {
+        let mut by_daemon: BTreeMap<usize, Vec<TaskToRestart>> = BTreeMap::new();
+        for mut task in tasks {
+            let daemon = self.daemon_index(task.task_id);
+            task.task_id = self.local_id(task.task_id);
+            by_daemon.entry(daemon).or_default().push(task);
+        }
+        for (daemon, tasks) in by_daemon {
+            self.client(daemon)?.restart_tasks(tasks).await?;
+        }
+        Ok(())
+    }
+
+    async fn enqueue_tasks(&mut self, ids: Vec<usize>) -> Result<()> {
+        for (daemon, ids) in self.split_ids(ids) {
+            self.client(daemon)?.enqueue_tasks(ids).await?;
+        }
+        Ok(())
+    }
+
+    async fn pause_tasks(&mut self, ids: Vec<usize>) -> Result<()> {
+        for (daemon, ids) in self.split_ids(ids) {
+            self.client(daemon)?.pause_tasks(ids).await?;
+        }
+        Ok(())
+    }
+
+    async fn kill_tasks(&mut self, ids: Vec<usize>) -> Result<()> {
+        for (daemon, ids) in self.split_ids(ids) {
+            self.client(daemon)?.kill_tasks(ids).await?;
+        }
+        Ok(())
+    }
+
+    async fn remove_tasks(&mut self, ids: Vec<usize>) -> Result<()> {
+        for (daemon, ids) in self.split_ids(ids) {
+            self.client(daemon)?.remove_tasks(ids).await?;
+        }
+        Ok(())
+    }
+
+    async fn set_priorities(&mut self, priorities: Vec<(usize, i32)>) -> Result<()> {
+        let mut by_daemon: BTreeMap<usize, Vec<(usize, i32)>> = BTreeMap::new();
+        for (id, priority) in priorities {
+            by_daemon
+                .entry(self.daemon_index(id))
+                .or_default()
+                .push((self.local_id(id), priority));
+        }
+        for (daemon, priorities) in by_daemon {
+            self.client(daemon)?.set_priorities(priorities).await?;
+        }
+        Ok(())
+    }
+
+    async fn switch_tasks(&mut self, task_id_1: usize, task_id_2: usize) -> Result<()> {
+        let daemon = self.daemon_index(task_id_1);
+        if daemon != self.daemon_index(task_id_2) {
+            return Err(anyhow!("can't swap tasks of different daemons"));
+        }
+        let (task_id_1, task_id_2) = (self.local_id(task_id_1), self.local_id(task_id_2));
+        self.client(daemon)?
+            .switch_tasks(task_id_1, task_id_2)
+            .await
+    }
+
+    async fn add_task(&mut self, origin: usize, mut request: AddRequest) -> Result<usize> {
+        let (daemon, origin) = (self.daemon_index(origin), self.local_id(origin));
+        // Tasks can only depend on tasks of the same daemon
+        request.dependencies = request
+            .dependencies
+            .iter()
+            .filter(|id| self.daemon_index(**id) == daemon)
+            .map(|id| self.local_id(*id))
+            .collect();
+        request.group = self
+            .local_group(daemon, &request.group)
+            .ok_or_else(|| anyhow!("group {} is on another daemon", request.group))?;
+        let id = self.client(daemon)?.add_task(origin, request).await?;
+        Ok(global_id(daemon, id))
+    }
+
+    async fn clean_tasks(&mut self, successful_only: bool, group: Option<String>) -> Result<()> {
+        let mut errors = Vec::new();
+        let groups: Vec<Option<Option<String>>> = (0..self.daemons.len())
+            .map(|daemon| match &group {
+                Some(group) => self.local_group(daemon, group).map(Some),
+                None => Some(None),
+            })
+            .collect();
+        for (daemon, group) in self.daemons.iter_mut().zip(groups) {
+            // Only the daemon the group is on
+            let Some(group) = group else {
+                continue;
+            };
+            let result = match &mut daemon.client {
+                Some(client) => client.clean_tasks(successful_only, group).await,
+                None => Err(anyhow!("Not connected")),
+            };
+            if let Err(e) = result {
+                errors.push(format!("{}: {}", daemon.name, e));
+            }
+        }
+        all_succeeded(errors)
+    }
+
+    async fn reset(&mut self) -> Result<()> {
+        let mut errors = Vec::new();
+        for daemon in &mut self.daemons {
+            let result = match &mut daemon.client {
+                Some(client) => client.reset().await,
+                None => Err(anyhow!("Not connected")),
+            };
+            if let Err(e) = result {
+                errors.push(format!("{}: {}", daemon.name, e));
+            }
+        }
+        all_succeeded(errors)
+    }
+
+    async fn set_task_env(
+        &mut self,
+        task_id: usize,
+        key: String,
+        value: Option<String>,
+    ) -> Result<()> {
+        let (daemon, task_id) = (self.daemon_index(task_id), self.local_id(task_id));
+        self.client(daemon)?.set_task_env(task_id, key, value).await
+    }
+
+    async fn get_log(&mut self, id: usize, lines: Option<usize>) -> Result<TaskLog> {
+        let (daemon, id) = (self.daemon_index(id), self.local_id(id));
+        self.client(daemon)?.get_log(id, lines).await
+    }
+
+    async fn start_log_stream(&mut self, id: usize, lines: Option<usize>) -> Result<String> {
+        let (daemon, id) = (self.daemon_index(id), self.local_id(id));
+        self.streaming = Some(daemon);
+        self.client(daemon)?.start_log_stream(id, lines).await
+    }
+
+    async fn receive_stream_chunk(&mut self) -> Result<Option<String>> {
+        match self.streaming {
+            Some(daemon) => self.client(daemon)?.receive_stream_chunk().await,
+            None => Ok(None),
+        }
+    }
+
+    async fn reconnect(&mut self) -> Result<()> {
+        let mut first_error = None;
+        for daemon in &mut self.daemons {
+            let Some(client) = &mut daemon.client else {
+                continue;
+            };
+            match client.reconnect().await {
+                Ok(()) => daemon.error = None,
+                Err(e) => {
+                    daemon.error = Some(e.to_string());
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        match first_error {
+            Some(e) if self.daemons.iter().all(|daemon| daemon.error.is_some()) => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    fn daemon_status(&self) -> Vec<DaemonStatus> {
+        self.daemons
+            .iter()
+            .map(|daemon| DaemonStatus {
+                name: daemon.name.clone(),
+                error: daemon.error.clone(),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,106 @@
+use pueue_lib::state::State;
+use std::collections::{HashMap, HashSet};
+
+/// How a line in the dependency view relates to its root task
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepRelation {
+    /// The task the graph is drawn for
+    Root,
+    /// A task the root (transitively) depends on
+    DependsOn,
+    /// A task that (transitively) depends on the root
+    RequiredBy,
+}
+
+/// A single row of the flattened dependency tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DepLine {
+    pub task_id: usize,
+    /// Distance from the root task (0 for the root itself)
+    pub depth: usize,
+    pub relation: DepRelation,
+    /// Listed further up already, with its own dependencies or dependents
+    pub repeated: bool,
+}
+
+/// Flatten the dependency DAG around each root into display lines.
+/// Each root is followed by the tasks it depends on, then the tasks that depend on it.
+/// A task reached again through another path is listed there as `repeated`, without its own
+/// dependencies or dependents, so graphs whose branches keep rejoining stay small. This also
+/// cuts off cycles, which pueue shouldn't allow anyway.
+pub fn dependency_lines(state: &State, roots: &[usize]) -> Vec<DepLine> {
+    let dependents = dependents(state);
+    let mut lines = Vec::new();
+    for &root in roots {
+        if !state.tasks.contains_key(&root) {
+            continue;
+        }
+        lines.push(DepLine {
+            task_id: root,
+            depth: 0,
+            relation: DepRelation::Root,
+            repeated: false,
+        });
+        let mut visited = HashSet::from([root]);
+        push_tree(
+            root,
+            1,
+            DepRelation::DependsOn,
+            &|id| {
+                let mut dependencies = state
+                    .tasks
+                    .get(&id)
+                    .map(|task| task.dependencies.clone())
+                    .unwrap_or_default();
+                dependencies.sort();
+                dependencies
+            },
+            &mut visited,
+            &mut lines,
+        );
+        let mut visited = HashSet::from([root]);
+        push_tree(
+            root,
+            1,
+            DepRelation::RequiredBy,
+            &|id| dependents.get(&id).cloned().unwrap_or_default(),
+            &mut visited,
+            &mut lines,
+        );
+    }
+    lines
+}
+
+/// Ids of the tasks that list each task as a dependency, in order
+fn dependents(state: &State) -> HashMap<usize, Vec<usize>> {
+    let mut dependents: HashMap<usize, Vec<usize>> = HashMap::new();
+    for task in state.tasks.values() {
+        for dependency in &task.dependencies {
+            dependents.entry(*dependency).or_default().push(task.id);
+        }
+    }
+    dependents
+}
+
+/// Add the tasks linked to `task_id` one way, depth first
+fn push_tree(
+    task_id: usize,
+    depth: usize,
+    relation: DepRelation,
+    links: &impl Fn(usize) -> Vec<usize>,
+    visited: &mut HashSet<usize>,
+    lines: &mut Vec<DepLine>,
+) {
+    for linked in links(task_id) {
+        let repeated = !visited.insert(linked);
+        lines.push(DepLine {
+            task_id: linked,
+            depth,
+            relation,
+            repeated,
+        });
+        if !repeated {
+            push_tree(linked, depth + 1, relation, links, visited, lines);
+        }
+    }
+}
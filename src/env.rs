@@ -0,0 +1,224 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use std::collections::{BTreeMap, HashMap};
+
+/// Text input currently being typed in the environment view
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvInput {
+    /// Filter variables by name or value
+    Filter,
+    /// New value for an existing variable
+    Edit(String),
+    /// A new variable, as NAME=value
+    Add,
+}
+
+/// A row of the environment view
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvRow<'a> {
+    pub key: &'a str,
+    /// Value in the viewed task (or the first task when diffing)
+    pub value: Option<&'a str>,
+    /// Value in the second task when diffing
+    pub other: Option<&'a str>,
+    /// Changed from the task's environment
+    pub modified: bool,
+}
+
+/// State of the environment viewer
+#[derive(Debug)]
+pub struct EnvState {
+    pub task_id: usize,
+    /// Environment the task was queued with
+    pub original: BTreeMap<String, String>,
+    /// Working copy, edited before restarting the task
+    pub envs: BTreeMap<String, String>,
+    /// Second task and its environment when diffing two tasks
+    pub compare: Option<(usize, BTreeMap<String, String>)>,
+    pub filter: String,
+    /// Index of the highlighted row
+    pub selected: usize,
+    /// Input being typed and its buffer
+    pub input: Option<(EnvInput, String)>,
+}
+
+impl EnvState {
+    pub fn new(task_id: usize, envs: &HashMap<String, String>) -> Self {
+        let envs: BTreeMap<String, String> =
+            envs.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        Self {
+            task_id,
+            original: envs.clone(),
+            envs,
+            compare: None,
+            filter: String::new(),
+            selected: 0,
+            input: None,
+        }
+    }
+
+    /// Diff the environments of two tasks
+    pub fn diff(
+        task_id: usize,
+        envs: &HashMap<String, String>,
+        other_id: usize,
+        other_envs: &HashMap<String, String>,
+    ) -> Self {
+        let mut env_state = Self::new(task_id, envs);
+        env_state.compare = Some((
+            other_id,
+            other_envs
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+        ));
+        env_state
+    }
+
+    /// Whether the working copy differs from the task's environment
+    pub fn is_modified(&self) -> bool {
+        self.envs != self.original
+    }
+
+    /// Rows matching the filter. When diffing, only variables that differ are shown.
+    pub fn rows(&self) -> Vec<EnvRow<'_>> {
+        let filter = self.filter.to_lowercase();
+        let matches = |key: &str, value: Option<&str>, other: Option<&str>| {
+            filter.is_empty()
+                || key.to_lowercase().contains(&filter)
+                || value.is_some_and(|v| v.to_lowercase().contains(&filter))
+                || other.is_some_and(|v| v.to_lowercase().contains(&filter))
+        };
+
+        if let Some((_, other_envs)) = &self.compare {
+            let mut keys: Vec<&String> = self.envs.keys().chain(other_envs.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            keys.into_iter()
+                .map(|key| EnvRow {
+                    key,
+                    value: self.envs.get(key).map(String::as_str),
+                    other: other_envs.get(key).map(String::as_str),
+                    modified: false,
+                })
+                .filter(|row| row.value != row.other)
+                .filter(|row| matches(row.key, row.value, row.other))
+                .collect()
+        } else {
+            // Include removed variables, so the user can see what will be unset
+            let mut keys: Vec<&String> = self.envs.keys().chain(self.original.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            keys.into_iter()
+                .map(|key| {
+                    let value = self.envs.get(key).map(String::as_str);
+                    EnvRow {
+                        key,
+                        value,
+                        other: None,
+                        modified: value != self.original.get(key).map(String::as_str),
+                    }
+                })
+                .filter(|row| matches(row.key, row.value, None))
+                .collect()
+        }
+    }
+
+    pub(crate) fn handle_key(&mut self, key: KeyEvent, page_height: u16) -> bool {
+        if let Some((input, buffer)) = &mut self.input {
+            match key.code {
+                KeyCode::Esc => {
+                    if *input == EnvInput::Filter {
+                        self.filter.clear();
+                    }
+                    self.input = None;
+                }
+                KeyCode::Enter => {
+                    match input {
+                        EnvInput::Filter => {}
+                        EnvInput::Edit(name) => {
+                            self.envs.insert(name.clone(), buffer.clone());
+                        }
+                        EnvInput::Add => {
+                            if let Some((name, value)) = buffer.split_once('=')
+                                && !name.trim().is_empty()
+                            {
+                                self.envs.insert(name.trim().to_string(), value.to_string());
+                            }
+                        }
+                    }
+                    self.input = None;
+                }
+                KeyCode::Backspace => {
+                    buffer.pop();
+                    if *input == EnvInput::Filter {
+                        self.filter = buffer.clone();
+                    }
+                }
+                KeyCode::Char(c) => {
+                    buffer.push(c);
+                    if *input == EnvInput::Filter {
+                        self.filter = buffer.clone();
+                    }
+                }
+                _ => return false,
+            }
+            self.clamp_selection();
+            return true;
+        }
+
+        let row_count = self.rows().len();
+        let selected_key = self
+            .rows()
+            .get(self.selected)
+            .map(|row| row.key.to_string());
+        let editable = self.compare.is_none();
+
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.selected = self.selected.saturating_add(1);
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.selected = self.selected.saturating_sub(1);
+            }
+            KeyCode::PageDown => {
+                self.selected = self.selected.saturating_add(page_height as usize);
+            }
+            KeyCode::PageUp => {
+                self.selected = self.selected.saturating_sub(page_height as usize);
+            }
+            KeyCode::Home | KeyCode::Char('g') => {
+                self.selected = 0;
+            }
+            KeyCode::End | KeyCode::Char('G') => {
+                self.selected = row_count.saturating_sub(1);
+            }
+            KeyCode::Char('/') => {
+                self.input = Some((EnvInput::Filter, self.filter.clone()));
+            }
+            KeyCode::Char('e') | KeyCode::Enter if editable => {
+                if let Some(name) = selected_key {
+                    let value = self.envs.get(&name).cloned().unwrap_or_default();
+                    self.input = Some((EnvInput::Edit(name), value));
+                }
+            }
+            KeyCode::Char('a') if editable => {
+                self.input = Some((EnvInput::Add, String::new()));
+            }
+            KeyCode::Char('x') | KeyCode::Delete if editable => {
+                if let Some(name) = selected_key {
+                    self.envs.remove(&name);
+                }
+            }
+            KeyCode::Char('u') if editable => {
+                self.envs = self.original.clone();
+            }
+            _ => return false,
+        }
+        self.clamp_selection();
+        true
+    }
+
+    fn clamp_selection(&mut self) {
+        self.selected = self.selected.min(self.rows().len().saturating_sub(1));
+    }
+}
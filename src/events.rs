@@ -0,0 +1,124 @@
+use pueue_lib::state::State;
+use pueue_lib::task::{Task, TaskResult, TaskStatus};
+use std::collections::VecDeque;
+
+/// Number of events kept, older ones are dropped
+pub const MAX_EVENTS: usize = 1000;
+
+/// Something that happened to a task or in the background, for the event panel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub at: jiff::Timestamp,
+    /// Task the event is about
+    pub task_id: Option<usize>,
+    pub message: String,
+    pub error: bool,
+}
+
+/// The most recent events, newest first
+#[derive(Debug, Default)]
+pub struct EventLog {
+    events: VecDeque<Event>,
+}
+
+impl EventLog {
+    pub fn push(&mut self, event: Event) {
+        self.events.push_front(event);
+        self.events.truncate(MAX_EVENTS);
+    }
+
+    /// The events of one task, or all events
+    pub fn filtered(&self, task_id: Option<usize>) -> impl Iterator<Item = &Event> {
+        self.events
+            .iter()
+            .filter(move |event| task_id.is_none() || event.task_id == task_id)
+    }
+}
+
+/// State of the event panel
+#[derive(Debug, Default)]
+pub struct EventsState {
+    /// Index of the highlighted event
+    pub selected: usize,
+    /// Only show the events of this task
+    pub task_filter: Option<usize>,
+}
+
+/// Ids of the tasks that were in the old state and have changed status since
+pub fn status_changes(old: &State, new: &State) -> Vec<usize> {
+    new.tasks
+        .iter()
+        .filter(|(id, task)| {
+            old.tasks.get(id).is_some_and(|old| {
+                std::mem::discriminant(&old.status) != std::mem::discriminant(&task.status)
+            })
+        })
+        .map(|(id, _)| *id)
+        .collect()
+}
+
+/// Events for the tasks added, removed or changing status between two states.
+/// Starts and ends are timed by the daemon, anything else by when it was seen.
+/// `unreachable` tells whether a task's daemon can't be reached, so its tasks missing
+/// from the new state aren't taken as removed.
+pub fn task_changes(
+    old: &State,
+    new: &State,
+    now: jiff::Timestamp,
+    unreachable: impl Fn(usize) -> bool,
+) -> Vec<Event> {
+    let mut events = Vec::new();
+    for (id, task) in &new.tasks {
+        if !old.tasks.contains_key(id) {
+            events.push(Event {
+                at: now,
+                task_id: Some(*id),
+                message: format!(
+                    "Added ({}): {}",
+                    crate::ui::status_display(&task.status),
+                    task.command
+                ),
+                error: false,
+            });
+        }
+    }
+    for id in status_changes(old, new) {
+        events.push(status_event(id, &old.tasks[&id], &new.tasks[&id], now));
+    }
+    for (id, task) in &old.tasks {
+        if !new.tasks.contains_key(id) && !unreachable(*id) {
+            events.push(Event {
+                at: now,
+                task_id: Some(*id),
+                message: format!("Removed: {}", task.command),
+                error: false,
+            });
+        }
+    }
+    events.sort_by_key(|event| event.at);
+    events
+}
+
+fn status_event(id: usize, old: &Task, new: &Task, now: jiff::Timestamp) -> Event {
+    let timestamp = |secs: i64| jiff::Timestamp::from_second(secs).unwrap_or(now);
+    let (at, message, error) = match &new.status {
+        TaskStatus::Running { .. } if matches!(old.status, TaskStatus::Paused { .. }) => {
+            (now, "Resumed".to_string(), false)
+        }
+        TaskStatus::Running { start, .. } => {
+            (timestamp(start.timestamp()), "Started".to_string(), false)
+        }
+        TaskStatus::Done { end, result, .. } => (
+            timestamp(end.timestamp()),
+            format!("Finished: {}", crate::ui::status_display(&new.status)),
+            !matches!(result, TaskResult::Success),
+        ),
+        status => (now, crate::ui::status_display(status), false),
+    };
+    Event {
+        at,
+        task_id: Some(id),
+        message,
+        error,
+    }
+}
@@ -0,0 +1,229 @@
+use crate::daemons::DaemonStatus;
+use anyhow::{Context, Result};
+use pueue_lib::state::State;
+use pueue_lib::task::{TaskResult, TaskStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// A finished run of a task, as kept in the history file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// The task's id on its daemon, see [`crate::daemons::local_id`]
+    #[serde(default)]
+    pub task_id: usize,
+    /// The task's daemon, when monitoring several
+    #[serde(default)]
+    pub host: Option<String>,
+    pub command: String,
+    pub path: PathBuf,
+    pub group: String,
+    pub label: Option<String>,
+    /// Unix timestamps in seconds
+    pub start: i64,
+    pub end: i64,
+    /// As shown in the status column, e.g. "Failed (1)"
+    pub result: String,
+    pub success: bool,
+}
+
+impl RunRecord {
+    pub fn duration(&self) -> i64 {
+        (self.end - self.start).max(0)
+    }
+
+    /// Identifies a run, so it's only recorded once however many polls see it
+    fn key(&self) -> RunKey {
+        (
+            self.host.clone(),
+            self.task_id,
+            self.command.clone(),
+            self.path.clone(),
+            self.start,
+            self.end,
+        )
+    }
+}
+
+type RunKey = (Option<String>, usize, String, PathBuf, i64, i64);
+
+/// Statistics of the runs of one command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandStats {
+    pub command: String,
+    pub runs: usize,
+    pub failures: usize,
+    /// Median and 95th percentile duration in seconds
+    pub p50: i64,
+    pub p95: i64,
+}
+
+impl CommandStats {
+    pub fn failure_rate(&self) -> f64 {
+        self.failures as f64 / self.runs as f64
+    }
+}
+
+/// Finished runs, kept in a JSON lines file so they outlive the tasks in pueue
+#[derive(Debug)]
+pub struct History {
+    path: PathBuf,
+    records: Vec<RunRecord>,
+    seen: HashSet<RunKey>,
+}
+
+impl History {
+    /// Read the history file, which doesn't need to exist yet. Lines that can't be parsed
+    /// are skipped.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to read history file {}", path.display()));
+            }
+        };
+        let records: Vec<RunRecord> = content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+        let seen = records.iter().map(RunRecord::key).collect();
+        Ok(History {
+            path: path.to_path_buf(),
+            records,
+            seen,
+        })
+    }
+
+    /// Add the finished tasks of the state that aren't recorded yet, returning how many
+    pub fn record(&mut self, state: &State, daemons: &[DaemonStatus]) -> Result<usize> {
+        let mut new_records = Vec::new();
+        for (id, task) in &state.tasks {
+            let TaskStatus::Done {
+                start, end, result, ..
+            } = &task.status
+            else {
+                continue;
+            };
+            let record = RunRecord {
+                task_id: crate::daemons::local_id(*id, daemons.len()),
+                host: crate::ui::host_name(daemons, *id).map(String::from),
+                command: task.command.clone(),
+                path: task.path.clone(),
+                group: task.group.clone(),
+                label: task.label.clone(),
+                start: start.timestamp(),
+                end: end.timestamp(),
+                result: crate::ui::status_display(&task.status),
+                success: matches!(result, TaskResult::Success),
+            };
+            if !self.seen.contains(&record.key()) {
+                new_records.push(record);
+            }
+        }
+        if new_records.is_empty() {
+            return Ok(0);
+        }
+
+        let mut lines = String::new();
+        for record in &new_records {
+            lines.push_str(&serde_json::to_string(record)?);
+            lines.push('\n');
+        }
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(lines.as_bytes()))
+            .with_context(|| format!("Failed to write history file {}", self.path.display()))?;
+
+        // Only now are the runs recorded, so a failed write is tried again on the next refresh
+        self.seen.extend(new_records.iter().map(RunRecord::key));
+        let count = new_records.len();
+        self.records.extend(new_records);
+        Ok(count)
+    }
+
+    /// Statistics per command, the most run first
+    pub fn stats(&self) -> Vec<CommandStats> {
+        let mut by_command: BTreeMap<&str, Vec<&RunRecord>> = BTreeMap::new();
+        for record in &self.records {
+            by_command.entry(&record.command).or_default().push(record);
+        }
+        let mut stats: Vec<CommandStats> = by_command
+            .into_iter()
+            .map(|(command, records)| command_stats(command, &records))
+            .collect();
+        stats.sort_by(|a, b| b.runs.cmp(&a.runs).then_with(|| a.command.cmp(&b.command)));
+        stats
+    }
+
+    /// Statistics of one command, None if it never ran
+    pub fn command_stats(&self, command: &str) -> Option<CommandStats> {
+        let records: Vec<&RunRecord> = self
+            .records
+            .iter()
+            .filter(|record| record.command == command)
+            .collect();
+        (!records.is_empty()).then(|| command_stats(command, &records))
+    }
+
+    /// Durations of the last runs of a command in seconds, oldest first
+    pub fn recent_durations(&self, command: &str, count: usize) -> Vec<i64> {
+        let mut records: Vec<&RunRecord> = self
+            .records
+            .iter()
+            .filter(|record| record.command == command)
+            .collect();
+        records.sort_by_key(|record| record.end);
+        let skip = records.len().saturating_sub(count);
+        records[skip..]
+            .iter()
+            .map(|record| record.duration())
+            .collect()
+    }
+}
+
+fn command_stats(command: &str, records: &[&RunRecord]) -> CommandStats {
+    let mut durations: Vec<i64> = records.iter().map(|record| record.duration()).collect();
+    durations.sort();
+    CommandStats {
+        command: command.to_string(),
+        runs: records.len(),
+        failures: records.iter().filter(|record| !record.success).count(),
+        p50: percentile(&durations, 50),
+        p95: percentile(&durations, 95),
+    }
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[i64], percent: usize) -> i64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (percent * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// The values as a row of block characters, scaled from the smallest to the largest
+pub fn sparkline(values: &[i64]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let (Some(min), Some(max)) = (values.iter().min(), values.iter().max()) else {
+        return String::new();
+    };
+    values
+        .iter()
+        .map(|value| {
+            if max == min {
+                BARS[3]
+            } else {
+                BARS[((value - min) * 7 / (max - min)) as usize]
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,185 @@
+use crate::config::HookConfig;
+use crate::daemons::DaemonStatus;
+use crate::events::Event;
+use anyhow::{Context, Result, anyhow};
+use pueue_lib::task::Task;
+use regex::Regex;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// A configured hook, with its command pattern compiled
+#[derive(Debug)]
+pub struct Hook {
+    config: HookConfig,
+    command: Option<Regex>,
+}
+
+pub fn compile_hooks(configs: &[HookConfig]) -> Result<Vec<Hook>> {
+    configs
+        .iter()
+        .map(|config| {
+            if config.run.is_empty() {
+                return Err(anyhow!("Hook on {:?} has nothing to run", config.on));
+            }
+            let command = config
+                .command
+                .as_deref()
+                .map(|pattern| {
+                    Regex::new(pattern).with_context(|| format!("Invalid pattern {:?}", pattern))
+                })
+                .transpose()?;
+            Ok(Hook {
+                config: config.clone(),
+                command,
+            })
+        })
+        .collect()
+}
+
+impl Hook {
+    /// Whether the hook runs for a task changing from the old to the new version
+    pub fn matches(&self, old: &Task, new: &Task) -> bool {
+        let config = &self.config;
+        config.on.matches(&new.status)
+            && config.from.is_none_or(|from| from.matches(&old.status))
+            && (config.groups.is_empty()
+                || config
+                    .groups
+                    .iter()
+                    .any(|group| crate::daemons::group_matches(group, &new.group)))
+            && (config.labels.is_empty()
+                || new
+                    .label
+                    .as_ref()
+                    .is_some_and(|label| config.labels.contains(label)))
+            && self
+                .command
+                .as_ref()
+                .is_none_or(|regex| regex.is_match(&new.command))
+    }
+
+    /// The command to run for a task, with the placeholders filled in.
+    /// Placeholders in the task's values themselves (eg: a label of "{id}") are left as is.
+    pub fn command_line(&self, id: usize, task: &Task, daemons: &[DaemonStatus]) -> Vec<String> {
+        let id = crate::daemons::local_id(id, daemons.len()).to_string();
+        let status = crate::ui::status_display(&task.status);
+        let path = task.path.to_string_lossy();
+        let value = |name: &str| match name {
+            "id" => Some(id.as_str()),
+            "group" => Some(task.group.as_str()),
+            "label" => Some(task.label.as_deref().unwrap_or("")),
+            "status" => Some(status.as_str()),
+            "command" => Some(task.command.as_str()),
+            "path" => Some(path.as_ref()),
+            _ => None,
+        };
+        self.config
+            .run
+            .iter()
+            .map(|arg| fill_placeholders(arg, value))
+            .collect()
+    }
+}
+
+/// Replace each `{name}` in the template that `value` knows, in a single pass
+fn fill_placeholders<'a>(template: &str, value: impl Fn(&str) -> Option<&'a str>) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        let placeholder = rest[start + 1..]
+            .find('}')
+            .and_then(|end| Some((end, value(&rest[start + 1..start + 1 + end])?)));
+        match placeholder {
+            Some((end, text)) => {
+                filled.push_str(text);
+                rest = &rest[start + end + 2..];
+            }
+            None => {
+                filled.push('{');
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
+/// What a hook is given on stdin: the task as in `pui list --json`, and its previous status
+#[derive(serde::Serialize)]
+pub struct HookInput<'a> {
+    pub previous_status: String,
+    #[serde(flatten)]
+    pub task: crate::ui::TaskJson<'a>,
+}
+
+/// Run a hook in the background, in the task's directory if it exists here.
+/// Its result is sent as an event once it exits. Hooks keep running if pui quits first.
+pub fn spawn(
+    task_id: usize,
+    command: Vec<String>,
+    dir: Option<std::path::PathBuf>,
+    input: String,
+    events: UnboundedSender<Event>,
+) {
+    tokio::spawn(async move {
+        let result = run(&command, dir, input).await;
+        let (message, error) = match result {
+            Ok(output) if output.status.success() => {
+                (format!("Hook `{}` succeeded", command.join(" ")), false)
+            }
+            Ok(output) => {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let last_line = stderr.lines().rev().find(|line| !line.trim().is_empty());
+                let status = match output.status.code() {
+                    Some(code) => format!("exit code {}", code),
+                    None => "killed by a signal".to_string(),
+                };
+                let message = match last_line {
+                    Some(line) => format!(
+                        "Hook `{}` failed ({}): {}",
+                        command.join(" "),
+                        status,
+                        line.trim()
+                    ),
+                    None => format!("Hook `{}` failed ({})", command.join(" "), status),
+                };
+                (message, true)
+            }
+            Err(e) => (
+                format!("Hook `{}` failed: {:#}", command.join(" "), e),
+                true,
+            ),
+        };
+        // The receiver only goes away when the app quits
+        let _ = events.send(Event {
+            at: jiff::Timestamp::now(),
+            task_id: Some(task_id),
+            message,
+            error,
+        });
+    });
+}
+
+async fn run(
+    command: &[String],
+    dir: Option<std::path::PathBuf>,
+    input: String,
+) -> Result<std::process::Output> {
+    let mut cmd = tokio::process::Command::new(&command[0]);
+    cmd.args(&command[1..])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped());
+    if let Some(dir) = dir.filter(|dir| dir.is_dir()) {
+        cmd.current_dir(dir);
+    }
+    let mut child = cmd
+        .spawn()
+        .with_context(|| format!("Failed to run {}", command[0]))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that doesn't read its input closes stdin early, which is fine
+        let _ = stdin.write_all(input.as_bytes()).await;
+    }
+    Ok(child.wait_with_output().await?)
+}
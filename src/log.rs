@@ -0,0 +1,956 @@
+use crate::config::HighlightRule;
+use crate::ui;
+use anyhow::{Context, Result};
+use crossterm::event::{KeyCode, KeyEvent};
+use pueue_lib::task::Task;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Paragraph, Wrap};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Default cap on the number of lines kept in memory for a log view
+pub const DEFAULT_MAX_LINES: usize = 100_000;
+
+/// Default tab stop width in terminals is typically 8 characters
+const TAB: &str = "        ";
+
+/// Colours of the `[id]` prefixes in a merged log, by the task's position in the view
+const TASK_COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::Magenta,
+    Color::Yellow,
+    Color::Green,
+    Color::Blue,
+    Color::Red,
+];
+
+/// Text shown in the timestamp gutter for lines that were output before the log was opened
+const BEFORE_ATTACH: &str = "before attach";
+
+/// Timestamp gutter of the log viewer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Timestamps {
+    #[default]
+    Off,
+    /// Time since the log was opened
+    Relative,
+    /// Local time of day
+    Absolute,
+}
+
+impl Timestamps {
+    fn next(self) -> Self {
+        match self {
+            Timestamps::Off => Timestamps::Relative,
+            Timestamps::Relative => Timestamps::Absolute,
+            Timestamps::Absolute => Timestamps::Off,
+        }
+    }
+}
+
+/// A line of log output, with its wrapped row count cached for the last width it was wrapped at
+struct LogLine {
+    task_id: usize,
+    text: String,
+    /// When the line started arriving, None if it was output before the log was opened
+    arrived: Option<jiff::Timestamp>,
+    /// (width, rows)
+    wrapped: Option<(u16, u16)>,
+    /// Index of the first highlight rule matching the line
+    highlight: Option<usize>,
+}
+
+/// A compiled highlight rule
+#[derive(Debug, Clone)]
+pub struct Highlight {
+    regex: regex::Regex,
+    style: Style,
+    error: bool,
+}
+
+/// Compile highlight rules, failing on an invalid pattern or colour
+pub fn compile_highlights(rules: &[HighlightRule]) -> Result<Vec<Highlight>> {
+    rules
+        .iter()
+        .map(|rule| {
+            let regex = regex::Regex::new(&rule.pattern)
+                .with_context(|| format!("Invalid pattern {:?}", rule.pattern))?;
+            let color = Color::from_str(&rule.color)
+                .map_err(|_| anyhow::anyhow!("Invalid colour {:?}", rule.color))?;
+            let mut style = Style::default().fg(color);
+            if rule.bold {
+                style = style.add_modifier(Modifier::BOLD);
+            }
+            Ok(Highlight {
+                regex,
+                style,
+                error: rule.error,
+            })
+        })
+        .collect()
+}
+
+/// Prompt for saving the log to a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavePrompt {
+    pub path: String,
+    /// Remove colours and other terminal escape sequences
+    pub strip_ansi: bool,
+    /// Start the file with the task's command, status and timestamps
+    pub header: bool,
+}
+
+/// State of the log viewer.
+/// Output is indexed by line as it arrives, and each line's wrapped height is computed
+/// lazily and cached, so ingesting a chunk and scrolling only touch the lines involved
+/// rather than the whole log.
+pub struct LogState {
+    /// The task shown, or the first of a merged log
+    pub task_id: usize,
+    /// Tasks shown, more than one for a merged log
+    pub task_ids: Vec<usize>,
+    /// Tasks toggled off in a merged log
+    pub hidden: HashSet<usize>,
+    /// How each task's id is shown, see [`ui::task_label`]
+    labels: HashMap<usize, String>,
+    lines: VecDeque<LogLine>,
+    /// Line number (counting dropped lines) of each task's last line, while it hasn't ended
+    /// with a newline, so the task's next chunk continues it
+    open_lines: HashMap<usize, usize>,
+    /// Lines dropped from the front to stay under `max_lines`
+    dropped_lines: usize,
+    /// Size of the output received, including dropped lines
+    bytes: usize,
+    /// Maximum number of lines kept, older lines are dropped first
+    pub max_lines: usize,
+    /// First visible row: the line index and the wrapped row within that line
+    top: (usize, u16),
+    pub autoscroll: bool,
+    /// Following a running task's output, rather than showing a fixed snapshot
+    pub streaming: bool,
+    /// False if only the last lines of the output were fetched
+    pub output_complete: bool,
+    /// Task status when the output was fetched
+    pub status: Option<String>,
+    /// Soft-wrap long lines, otherwise they can be scrolled horizontally
+    pub wrap: bool,
+    /// Columns scrolled horizontally, when not wrapping
+    pub left: u16,
+    /// Show a gutter with line numbers
+    pub line_numbers: bool,
+    pub timestamps: Timestamps,
+    /// When the log was opened, which relative timestamps count from
+    pub attached_at: jiff::Timestamp,
+    /// Line number being typed after `:`
+    pub jump_input: Option<String>,
+    highlights: Vec<Highlight>,
+    /// Line number (counting dropped lines) of the highlighted line last jumped to
+    current_match: Option<usize>,
+    pub save_prompt: Option<SavePrompt>,
+    /// Result of the last action, shown at the bottom of the view
+    pub message: Option<String>,
+}
+
+impl std::fmt::Debug for LogState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogState")
+            .field("task_id", &self.task_id)
+            .field("lines", &self.lines.len())
+            .field("bytes", &self.bytes)
+            .field("top", &self.top)
+            .field("autoscroll", &self.autoscroll)
+            .field("streaming", &self.streaming)
+            .field("output_complete", &self.output_complete)
+            .finish()
+    }
+}
+
+impl LogState {
+    /// Log view of one or more tasks. With several tasks, their output is interleaved
+    /// and each line is prefixed with its task id.
+    pub fn new(task_ids: Vec<usize>) -> Self {
+        Self {
+            task_id: task_ids.first().copied().unwrap_or_default(),
+            task_ids,
+            hidden: HashSet::new(),
+            labels: HashMap::new(),
+            lines: VecDeque::new(),
+            open_lines: HashMap::new(),
+            dropped_lines: 0,
+            bytes: 0,
+            max_lines: DEFAULT_MAX_LINES,
+            top: (0, 0),
+            autoscroll: true,
+            streaming: true,
+            output_complete: true,
+            status: None,
+            wrap: true,
+            left: 0,
+            line_numbers: false,
+            timestamps: Timestamps::Off,
+            attached_at: jiff::Timestamp::now(),
+            jump_input: None,
+            highlights: Vec::new(),
+            current_match: None,
+            save_prompt: None,
+            message: None,
+        }
+    }
+
+    /// Show the tasks' ids as on their own daemon, when monitoring several
+    pub fn set_labels(&mut self, daemons: &[crate::daemons::DaemonStatus]) {
+        self.labels = self
+            .task_ids
+            .iter()
+            .map(|id| (*id, ui::task_label(daemons, *id)))
+            .collect();
+    }
+
+    /// A task's id as shown to the user
+    pub fn label(&self, task_id: usize) -> String {
+        self.labels
+            .get(&task_id)
+            .cloned()
+            .unwrap_or_else(|| task_id.to_string())
+    }
+
+    /// The shown task's id, for file names, eg: "3-build"
+    pub fn file_label(&self) -> String {
+        self.label(self.task_id)
+            .replace(|c: char| !c.is_alphanumeric() && c != '_', "-")
+    }
+
+    /// Set the rules to highlight lines with, and find the lines they match
+    pub fn set_highlights(&mut self, highlights: Vec<Highlight>) {
+        self.highlights = highlights;
+        for index in 0..self.lines.len() {
+            self.lines[index].highlight = self.match_highlight(&self.lines[index].text);
+        }
+    }
+
+    fn match_highlight(&self, text: &str) -> Option<usize> {
+        if self.highlights.is_empty() {
+            return None;
+        }
+        // Colours would get in the way of patterns like `\berror\b`
+        let text = if text.contains('\x1b') {
+            std::borrow::Cow::Owned(strip_ansi_codes(text))
+        } else {
+            std::borrow::Cow::Borrowed(text)
+        };
+        self.highlights.iter().position(|h| h.regex.is_match(&text))
+    }
+
+    /// Number of the first line matching an error rule (counting dropped lines)
+    pub fn first_error_line(&self) -> Option<usize> {
+        self.find_highlighted(0..self.lines.len(), true)
+            .map(|index| self.dropped_lines + index + 1)
+    }
+
+    /// First shown line in the range that is highlighted, optionally only by an error rule
+    fn find_highlighted(
+        &self,
+        mut range: impl Iterator<Item = usize>,
+        error: bool,
+    ) -> Option<usize> {
+        range.find(|&index| {
+            let line = &self.lines[index];
+            !self.hidden.contains(&line.task_id)
+                && line
+                    .highlight
+                    .is_some_and(|rule| !error || self.highlights[rule].error)
+        })
+    }
+
+    fn is_merged(&self) -> bool {
+        self.task_ids.len() > 1
+    }
+
+    /// Append a chunk of output from one of the tasks shown
+    pub fn push_str(&mut self, task_id: usize, chunk: &str) {
+        self.append(task_id, chunk, None);
+    }
+
+    /// Append a chunk of streamed output, recording when it arrived
+    pub fn push_streamed(&mut self, task_id: usize, chunk: &str, arrived: jiff::Timestamp) {
+        self.append(task_id, chunk, Some(arrived));
+    }
+
+    fn append(&mut self, task_id: usize, chunk: &str, arrived: Option<jiff::Timestamp>) {
+        if chunk.is_empty() {
+            return;
+        }
+        self.bytes += chunk.len();
+
+        let pieces: Vec<&str> = chunk.split('\n').collect();
+        let last = pieces.len() - 1;
+        for (i, piece) in pieces.into_iter().enumerate() {
+            // Continue the task's unfinished line, unless it has been dropped since
+            let open = if i == 0 {
+                self.open_lines
+                    .remove(&task_id)
+                    .and_then(|line_number| line_number.checked_sub(self.dropped_lines))
+            } else {
+                None
+            };
+            // A trailing newline doesn't start a line until more output arrives
+            if i == last && piece.is_empty() && open.is_none() {
+                break;
+            }
+            let index = match open {
+                Some(index) => {
+                    let line = &mut self.lines[index];
+                    line.text.push_str(piece);
+                    line.wrapped = None;
+                    let highlight = self.match_highlight(&self.lines[index].text);
+                    self.lines[index].highlight = highlight;
+                    index
+                }
+                None => {
+                    self.lines.push_back(LogLine {
+                        task_id,
+                        text: piece.to_string(),
+                        arrived,
+                        wrapped: None,
+                        highlight: self.match_highlight(piece),
+                    });
+                    self.lines.len() - 1
+                }
+            };
+            if i == last {
+                self.open_lines.insert(task_id, self.dropped_lines + index);
+            }
+        }
+
+        let excess = self.lines.len().saturating_sub(self.max_lines.max(1));
+        if excess > 0 {
+            self.lines.drain(..excess);
+            self.dropped_lines += excess;
+            self.top = if self.top.0 >= excess {
+                (self.top.0 - excess, self.top.1)
+            } else {
+                (0, 0)
+            };
+        }
+    }
+
+    /// Number of lines received, including dropped ones
+    pub fn line_count(&self) -> usize {
+        self.dropped_lines + self.lines.len()
+    }
+
+    /// The lines currently shown, without their line endings.
+    /// Lines of a merged log are prefixed with their task id.
+    pub fn lines(&self) -> impl Iterator<Item = String> {
+        self.lines
+            .iter()
+            .filter(|line| !self.hidden.contains(&line.task_id))
+            .map(|line| self.display_text(line))
+    }
+
+    fn display_text(&self, line: &LogLine) -> String {
+        if self.is_merged() {
+            format!("[{}] {}", self.label(line.task_id), line.text)
+        } else {
+            line.text.clone()
+        }
+    }
+
+    /// Colour of a task's prefix in a merged log
+    fn task_color(&self, task_id: usize) -> Color {
+        let position = self
+            .task_ids
+            .iter()
+            .position(|id| *id == task_id)
+            .unwrap_or_default();
+        TASK_COLORS[position % TASK_COLORS.len()]
+    }
+
+    /// Show or hide a task of a merged log
+    pub fn toggle_task(&mut self, task_id: usize) {
+        if !self.hidden.remove(&task_id) {
+            self.hidden.insert(task_id);
+        }
+        // Line heights change, so restart from the top of the current line
+        self.top.1 = 0;
+    }
+
+    /// Text of the lines in view, without escape sequences
+    pub fn visible_text(&mut self, page_height: u16, page_width: u16) -> String {
+        let mut text = Vec::new();
+        // Rows of the first line scrolled out of view count against the page
+        // Counted in usize, as a single huge line can take up to u16::MAX rows
+        let mut rows = 0;
+        let mut index = self.top.0;
+        while index < self.lines.len() && rows < page_height as usize + self.top.1 as usize {
+            if self.rows(index, page_width) > 0 {
+                rows += self.rows(index, page_width) as usize;
+                text.push(strip_ansi_codes(&self.display_text(&self.lines[index])));
+            }
+            index += 1;
+        }
+        text.join("\n")
+    }
+
+    /// Text of the highlighted line last jumped to with n/N/E, without escape sequences
+    pub fn match_text(&self) -> Option<String> {
+        let index = self.current_match?.checked_sub(self.dropped_lines + 1)?;
+        let line = self.lines.get(index)?;
+        Some(strip_ansi_codes(&self.display_text(line)))
+    }
+
+    /// Write the log to a file, optionally starting with a header and without escape sequences.
+    /// Returns the number of lines written.
+    pub fn save(
+        &self,
+        path: &Path,
+        header: Option<&str>,
+        strip_ansi: bool,
+    ) -> std::io::Result<usize> {
+        let mut file = std::fs::File::create(path)?;
+        self.write_to(&mut file, header, strip_ansi)
+    }
+
+    /// Write the log to a new temporary file only the user can read, for an external viewer.
+    /// The file is deleted when the returned handle is dropped.
+    pub fn save_temporary(&self) -> std::io::Result<tempfile::NamedTempFile> {
+        let mut file = tempfile::Builder::new()
+            .prefix(&format!("pui-task-{}-", self.file_label()))
+            .suffix(".log")
+            .tempfile()?;
+        self.write_to(file.as_file_mut(), None, false)?;
+        Ok(file)
+    }
+
+    fn write_to(
+        &self,
+        out: &mut impl std::io::Write,
+        header: Option<&str>,
+        strip_ansi: bool,
+    ) -> std::io::Result<usize> {
+        let mut content = String::with_capacity(self.bytes);
+        if let Some(header) = header {
+            content.push_str(header);
+        }
+        let mut count = 0;
+        for line in self.lines() {
+            if strip_ansi {
+                content.push_str(&strip_ansi_codes(&line));
+            } else {
+                content.push_str(&line);
+            }
+            content.push('\n');
+            count += 1;
+        }
+        out.write_all(content.as_bytes())?;
+        Ok(count)
+    }
+
+    /// Lines to render for a page of the given height, and how many wrapped rows of the
+    /// first one are scrolled out of view
+    pub fn visible_lines(&self, page_height: u16) -> (Vec<Line<'static>>, u16) {
+        let lines = self
+            .lines
+            .iter()
+            .skip(self.top.0)
+            .filter(|line| !self.hidden.contains(&line.task_id))
+            // Every line takes at least one row
+            .take(page_height as usize + 1)
+            .map(|line| {
+                let text = line.text.replace('\t', TAB);
+                let mut spans = Vec::new();
+                if self.is_merged() {
+                    spans.push(Span::styled(
+                        format!("[{}] ", self.label(line.task_id)),
+                        Style::default().fg(self.task_color(line.task_id)),
+                    ));
+                }
+                match line.highlight {
+                    Some(rule) => spans.extend(highlight_spans(text, &self.highlights[rule])),
+                    None => spans.push(Span::raw(text)),
+                }
+                Line::from(spans)
+            })
+            .collect();
+        (lines, self.top.1)
+    }
+
+    /// What's known about the output, for titles
+    pub fn summary(&self) -> String {
+        let mut parts = if self.is_merged() {
+            let ids: Vec<String> = self
+                .task_ids
+                .iter()
+                .filter(|id| !self.hidden.contains(id))
+                .map(|id| format!("#{}", self.label(*id)))
+                .collect();
+            vec![format!("Merged Log {}", ids.join(" "))]
+        } else {
+            vec![format!("Task Log #{}", self.label(self.task_id))]
+        };
+        if self.streaming {
+            parts.push("following".to_string());
+        } else if let Some(status) = &self.status {
+            parts.push(status.clone());
+        }
+        parts.push(format!(
+            "{} lines, {}",
+            self.line_count(),
+            ui::format_size(self.bytes)
+        ));
+        if !self.output_complete {
+            parts.push("truncated".to_string());
+        }
+        if !self.wrap {
+            parts.push("no wrap".to_string());
+        }
+        if self.dropped_lines > 0 {
+            parts.push(format!("first {} dropped", self.dropped_lines));
+        }
+        if self.is_merged() {
+            let mut hidden: Vec<&usize> = self.hidden.iter().collect();
+            hidden.sort();
+            if !hidden.is_empty() {
+                let ids: Vec<String> = hidden
+                    .iter()
+                    .map(|id| format!("#{}", self.label(**id)))
+                    .collect();
+                parts.push(format!("hidden {}", ids.join(" ")));
+            }
+            parts.push("1-9: toggle".to_string());
+        }
+        parts.join(" | ")
+    }
+
+    pub(crate) fn handle_key(&mut self, key: KeyEvent, page_height: u16, page_width: u16) -> bool {
+        if let Some(input) = &mut self.jump_input {
+            match key.code {
+                KeyCode::Esc => self.jump_input = None,
+                KeyCode::Enter => {
+                    if let Ok(line_number) = input.parse::<usize>() {
+                        self.jump_to_line(line_number, page_height, page_width);
+                    }
+                    self.jump_input = None;
+                }
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(c) if c.is_ascii_digit() => input.push(c),
+                _ => return false,
+            }
+            return true;
+        }
+
+        let text_width = self.text_width(page_width);
+        let found = match key.code {
+            KeyCode::Char('n') => Some((
+                self.find_highlighted(self.top.0 + 1..self.lines.len(), false),
+                "No more highlighted lines below",
+            )),
+            KeyCode::Char('N') => Some((
+                self.find_highlighted((0..self.top.0).rev(), false),
+                "No more highlighted lines above",
+            )),
+            KeyCode::Char('E') => Some((
+                self.find_highlighted(0..self.lines.len(), true),
+                "No errors found",
+            )),
+            _ => None,
+        };
+        if let Some((index, not_found)) = found {
+            match index {
+                Some(index) => {
+                    self.current_match = Some(self.dropped_lines + index + 1);
+                    self.jump_to_line(self.dropped_lines + index + 1, page_height, page_width);
+                }
+                None => self.message = Some(not_found.to_string()),
+            }
+            return true;
+        }
+
+        // Whether the key scrolls vertically, which stops following the output
+        let scrolled = match key.code {
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.scroll_down(1, page_width);
+                true
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.scroll_up(1, page_width);
+                true
+            }
+            KeyCode::PageUp | KeyCode::Char('b') => {
+                self.scroll_up(page_height, page_width);
+                true
+            }
+            KeyCode::PageDown | KeyCode::Char(' ') => {
+                self.scroll_down(page_height, page_width);
+                true
+            }
+            KeyCode::Home | KeyCode::Char('g') => {
+                self.top = (0, 0);
+                true
+            }
+            KeyCode::Char('d') => {
+                self.scroll_down(page_height / 2, page_width);
+                true
+            }
+            KeyCode::Char('u') => {
+                self.scroll_up(page_height / 2, page_width);
+                true
+            }
+            KeyCode::End | KeyCode::Char('G') => {
+                self.autoscroll = true;
+                false
+            }
+            KeyCode::Char(':') => {
+                self.jump_input = Some(String::new());
+                false
+            }
+            KeyCode::Char('w') => {
+                self.wrap = !self.wrap;
+                self.left = 0;
+                self.top.1 = 0;
+                false
+            }
+            KeyCode::Char('#') => {
+                self.line_numbers = !self.line_numbers;
+                self.top.1 = 0;
+                false
+            }
+            KeyCode::Char('T') => {
+                self.timestamps = self.timestamps.next();
+                self.top.1 = 0;
+                false
+            }
+            KeyCode::Char('h') | KeyCode::Left if !self.wrap => {
+                self.left = self.left.saturating_sub(TAB.len() as u16);
+                false
+            }
+            KeyCode::Char('l') | KeyCode::Right if !self.wrap => {
+                self.left = self
+                    .left
+                    .saturating_add(TAB.len() as u16)
+                    .min(self.max_left(page_height, text_width));
+                false
+            }
+            KeyCode::Char('0') if !self.wrap => {
+                self.left = 0;
+                false
+            }
+            KeyCode::Char('$') if !self.wrap => {
+                self.left = self.max_left(page_height, text_width);
+                false
+            }
+            KeyCode::Char(c @ '1'..='9') if self.is_merged() => {
+                let position = c as usize - '1' as usize;
+                let Some(&task_id) = self.task_ids.get(position) else {
+                    return false;
+                };
+                // Keep following the output, or stay on the current line
+                self.toggle_task(task_id);
+                false
+            }
+            _ => return false,
+        };
+        if scrolled {
+            self.autoscroll = false;
+        }
+
+        if self.autoscroll {
+            self.update_autoscroll(page_height, page_width);
+        } else {
+            // Clamp manual scrolling to the last possible position, so we can't overscroll into blank space.
+            let bottom = self.bottom(page_height, page_width);
+            self.top = self.top.min(bottom);
+        }
+        true
+    }
+
+    /// Scroll so the given line (numbered from 1, including dropped lines) is at the top
+    fn jump_to_line(&mut self, line_number: usize, page_height: u16, page_width: u16) {
+        let index = line_number
+            .saturating_sub(1)
+            .saturating_sub(self.dropped_lines)
+            .min(self.lines.len().saturating_sub(1));
+        self.top = (index, 0);
+        self.autoscroll = false;
+        let bottom = self.bottom(page_height, page_width);
+        self.top = self.top.min(bottom);
+    }
+
+    /// Width of the line number column, including its trailing space
+    fn number_width(&self) -> u16 {
+        if self.line_numbers {
+            self.line_count().max(1).to_string().len() as u16 + 1
+        } else {
+            0
+        }
+    }
+
+    /// Width of the gutter with line numbers and timestamps, including trailing spaces
+    pub fn gutter_width(&self) -> u16 {
+        let timestamp_width = match self.timestamps {
+            Timestamps::Off => 0,
+            _ => BEFORE_ATTACH.len() as u16 + 1,
+        };
+        self.number_width() + timestamp_width
+    }
+
+    /// A line's arrival time for the gutter
+    fn format_arrival(&self, arrived: Option<jiff::Timestamp>) -> String {
+        let Some(arrived) = arrived else {
+            return BEFORE_ATTACH.to_string();
+        };
+        match self.timestamps {
+            Timestamps::Off => String::new(),
+            Timestamps::Relative => {
+                let secs = (arrived.as_second() - self.attached_at.as_second()).max(0);
+                format!(
+                    "+{:02}:{:02}:{:02}",
+                    secs / 3600,
+                    secs % 3600 / 60,
+                    secs % 60
+                )
+            }
+            Timestamps::Absolute => arrived
+                .to_zoned(jiff::tz::TimeZone::system())
+                .strftime("%H:%M:%S")
+                .to_string(),
+        }
+    }
+
+    /// Width left for the log text, after the gutter
+    fn text_width(&self, page_width: u16) -> u16 {
+        page_width.saturating_sub(self.gutter_width()).max(1)
+    }
+
+    /// Line numbers and timestamps of the visible lines, with blank rows for wrapped continuations
+    pub fn gutter_lines(&self, page_height: u16, text_width: u16) -> Vec<Line<'static>> {
+        let number_width = self.number_width() as usize;
+        let timestamp_width = BEFORE_ATTACH.len();
+        let mut rows = Vec::new();
+        for (index, line) in self.lines.iter().enumerate().skip(self.top.0) {
+            if self.hidden.contains(&line.task_id) {
+                continue;
+            }
+            let line_rows = match line.wrapped {
+                _ if !self.wrap => 1,
+                Some((width, rows)) if width == text_width => rows,
+                _ => wrapped_rows(&self.display_text(line), text_width),
+            };
+            let mut row = String::new();
+            if self.line_numbers {
+                row.push_str(&format!(
+                    "{:>width$} ",
+                    self.dropped_lines + index + 1,
+                    width = number_width - 1
+                ));
+            }
+            if self.timestamps != Timestamps::Off {
+                row.push_str(&format!(
+                    "{:>timestamp_width$} ",
+                    self.format_arrival(line.arrived)
+                ));
+            }
+            rows.push(row);
+            rows.extend((1..line_rows).map(|_| String::new()));
+            if rows.len() >= page_height as usize + self.top.1 as usize {
+                break;
+            }
+        }
+        rows.into_iter()
+            .skip(self.top.1 as usize)
+            .take(page_height as usize)
+            .map(|row| Line::styled(row, Style::default().fg(Color::DarkGray)))
+            .collect()
+    }
+
+    /// Furthest horizontal scroll that still shows the end of the widest visible line
+    fn max_left(&self, page_height: u16, text_width: u16) -> u16 {
+        let widest = self
+            .visible_lines(page_height)
+            .0
+            .iter()
+            .map(Line::width)
+            .max()
+            .unwrap_or_default();
+        widest
+            .saturating_sub(text_width as usize)
+            .min(u16::MAX as usize) as u16
+    }
+
+    pub(crate) fn update_autoscroll(&mut self, page_height: u16, page_width: u16) {
+        if self.autoscroll {
+            self.top = self.bottom(page_height, page_width);
+        }
+    }
+
+    /// Number of rows a line takes when wrapped.
+    /// Uses ratatui's own wrapping algorithm (Paragraph::line_count) so scrolling matches
+    /// exactly what gets rendered.
+    /// Hidden lines take no rows.
+    fn rows(&mut self, index: usize, page_width: u16) -> u16 {
+        if self.hidden.contains(&self.lines[index].task_id) {
+            return 0;
+        }
+        if !self.wrap {
+            return 1;
+        }
+        let width = self.text_width(page_width);
+        if let Some((cached_width, rows)) = self.lines[index].wrapped
+            && cached_width == width
+        {
+            return rows;
+        }
+        let rows = wrapped_rows(&self.display_text(&self.lines[index]), width);
+        self.lines[index].wrapped = Some((width, rows));
+        rows
+    }
+
+    /// Top position that shows the last page of output
+    fn bottom(&mut self, page_height: u16, page_width: u16) -> (usize, u16) {
+        let mut remaining = page_height;
+        for index in (0..self.lines.len()).rev() {
+            let rows = self.rows(index, page_width);
+            if rows >= remaining {
+                return (index, rows - remaining);
+            }
+            remaining -= rows;
+        }
+        (0, 0)
+    }
+
+    fn scroll_down(&mut self, mut count: u16, page_width: u16) {
+        while count > 0 && self.top.0 < self.lines.len() {
+            let rows = self.rows(self.top.0, page_width);
+            let rows_below = rows.saturating_sub(1 + self.top.1);
+            if rows > 0 && count <= rows_below {
+                self.top.1 += count;
+                return;
+            }
+            let Some(next) =
+                (self.top.0 + 1..self.lines.len()).find(|&i| self.rows(i, page_width) > 0)
+            else {
+                self.top.1 = rows.saturating_sub(1);
+                return;
+            };
+            if rows > 0 {
+                count -= rows_below + 1;
+            }
+            self.top = (next, 0);
+        }
+    }
+
+    fn scroll_up(&mut self, mut count: u16, page_width: u16) {
+        while count > 0 {
+            if self.top.1 >= count {
+                self.top.1 -= count;
+                return;
+            }
+            let Some(previous) = (0..self.top.0)
+                .rev()
+                .find(|&i| self.rows(i, page_width) > 0)
+            else {
+                self.top.1 = 0;
+                return;
+            };
+            count -= self.top.1 + 1;
+            self.top = (previous, self.rows(previous, page_width) - 1);
+        }
+    }
+}
+
+/// Split a line into spans, styling the matches of a highlight rule.
+/// The whole line is styled if escape sequences split up the match.
+fn highlight_spans(text: String, highlight: &Highlight) -> Vec<Span<'static>> {
+    if !highlight.regex.is_match(&text) {
+        return vec![Span::styled(text, highlight.style)];
+    }
+    let mut spans = Vec::new();
+    let mut end = 0;
+    for m in highlight.regex.find_iter(&text) {
+        if m.start() > end {
+            spans.push(Span::raw(text[end..m.start()].to_string()));
+        }
+        spans.push(Span::styled(m.as_str().to_string(), highlight.style));
+        end = m.end();
+    }
+    if end < text.len() {
+        spans.push(Span::raw(text[end..].to_string()));
+    }
+    spans
+}
+
+/// Number of rows a line takes when wrapped at the given width
+fn wrapped_rows(text: &str, width: u16) -> u16 {
+    Paragraph::new(text.replace('\t', TAB))
+        .wrap(Wrap { trim: false })
+        .line_count(width.max(1))
+        .clamp(1, u16::MAX as usize) as u16
+}
+
+/// Header for a saved log, describing the task that produced it
+pub fn log_header(label: &str, task: &Task) -> String {
+    let mut header = format!("# Task: {}\n", label);
+    header.push_str(&format!("# Command: {}\n", task.command));
+    header.push_str(&format!("# Path: {}\n", task.path.display()));
+    header.push_str(&format!("# Status: {}\n", ui::status_display(&task.status)));
+    header.push_str(&format!(
+        "# Created: {}\n",
+        ui::format_local_time(task.created_at.timestamp())
+    ));
+    let (start, end) = task.start_and_end();
+    if let Some(start) = start {
+        header.push_str(&format!(
+            "# Started: {}\n",
+            ui::format_local_time(start.timestamp())
+        ));
+    }
+    if let Some(end) = end {
+        header.push_str(&format!(
+            "# Ended: {}\n",
+            ui::format_local_time(end.timestamp())
+        ));
+    }
+    header.push_str("#\n");
+    header
+}
+
+/// Remove ANSI escape sequences (colours, cursor movement, terminal titles) from a line
+pub fn strip_ansi_codes(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            stripped.push(c);
+            continue;
+        }
+        match chars.next() {
+            // CSI: parameters, then a final byte in @..~
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC: terminated by BEL or ESC \
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            // Other escapes are a single character
+            _ => {}
+        }
+    }
+    stripped
+}
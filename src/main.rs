@@ -1,13 +1,28 @@
+mod clipboard;
 mod config;
+mod daemons;
+mod deps;
+mod env;
+mod events;
 mod exec;
+mod history;
+mod hooks;
+mod log;
+mod notify;
 mod pueue_client;
 #[cfg(test)]
 mod tests;
 mod ui;
 
-use crate::config::{Config, CustomCommand, ParsedKey};
+use crate::config::{Config, CustomCommand, ParsedKey, StatusMatcher};
+use crate::daemons::Daemons;
+use crate::env::EnvState;
+use crate::events::{EventLog, EventsState};
+use crate::history::History;
+use crate::log::LogState;
 
 use anyhow::Result;
+use clap::{Parser, Subcommand};
 use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use futures::stream::StreamExt;
 use ratatui::{DefaultTerminal, Frame, layout::Rect, widgets::TableState};
@@ -15,18 +30,99 @@ use std::collections::HashSet;
 use std::time::Duration;
 use tokio::time::MissedTickBehavior;
 
-use crate::pueue_client::{PueueClient, PueueClientOps};
-use pueue_lib::message::TaskToRestart;
+use crate::pueue_client::{DaemonTarget, PueueClientOps};
+use pueue_lib::message::{AddRequest, TaskToRestart};
 use pueue_lib::state::State;
-use pueue_lib::task::TaskStatus;
+use pueue_lib::task::{TaskResult, TaskStatus};
+
+/// A terminal UI for Pueue
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Config file to use instead of the one in the platform config directory
+    #[arg(long, global = true)]
+    config: Option<std::path::PathBuf>,
+    /// Only show tasks matching this text (in the id, status, command or path)
+    #[arg(long, global = true)]
+    filter: Option<String>,
+    /// Sort the tasks by this field
+    #[arg(long, global = true, value_enum)]
+    sort: Option<SortField>,
+    /// Only show tasks in this group
+    #[arg(long, global = true)]
+    group: Option<String>,
+    /// Open the log viewer of this task, which must be in the `--group` if one is given
+    #[arg(long)]
+    task: Option<usize>,
+    /// Pueue config file of the daemon to connect to
+    #[arg(long, global = true)]
+    pueue_config: Option<std::path::PathBuf>,
+    /// Profile in the pueue config file to connect with
+    #[arg(long, global = true)]
+    profile: Option<String>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Print the task table and exit
+    List {
+        /// Print the tasks as a JSON array instead of a table, with the command and path
+        /// shortened as in the table, and in full as `full_command` and `full_path`
+        #[arg(long)]
+        json: bool,
+    },
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
     // Load config before initializing terminal (so errors print normally)
-    let config = Config::load()?;
+    let config = match &cli.config {
+        Some(path) if !path.exists() => {
+            anyhow::bail!("Config file {} not found", path.display())
+        }
+        Some(path) => Config::load_from_path(path)?,
+        None => Config::load()?,
+    };
+    let cli_target = cli.pueue_config.is_some() || cli.profile.is_some();
+    let target = DaemonTarget {
+        config: cli.pueue_config.or_else(|| config.pueue_config.clone()),
+        profile: cli.profile.or_else(|| config.pueue_profile.clone()),
+    };
+    // An explicit daemon on the command line overrides the configured list
+    let targets = if cli_target || config.daemons.is_empty() {
+        vec![("default".to_string(), target)]
+    } else {
+        config
+            .daemons
+            .iter()
+            .map(|daemon| {
+                let target = DaemonTarget {
+                    config: daemon.pueue_config.clone(),
+                    profile: daemon.profile.clone(),
+                };
+                (daemon.name.clone(), target)
+            })
+            .collect()
+    };
+    let filter_text = cli.filter.unwrap_or_default();
+    let sort_field = cli.sort.unwrap_or_default();
+
+    if let Some(Command::List { json }) = cli.command {
+        let pueue_client = Daemons::connect(targets)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to connect to Pueue daemon: {}", e))?;
+        let mut app = App::new(pueue_client, config);
+        app.group_filter = cli.group;
+        print!("{}", app.list(&filter_text, sort_field, json).await?);
+        return Ok(());
+    }
 
     let terminal = ratatui::init();
-    let pueue_client = match PueueClient::new().await {
+    let pueue_client = match Daemons::connect(targets).await {
         Ok(client) => client,
         Err(e) => {
             ratatui::restore();
@@ -34,13 +130,28 @@ async fn main() -> Result<()> {
             return Ok(());
         }
     };
-    let app = App::new(pueue_client, config);
+    let daemon = pueue_client.description();
+    let history = config.history_path().and_then(|path| History::load(&path));
+    let mut app = App::new(pueue_client, config);
+    match history {
+        Ok(history) => app.history = Some(history),
+        Err(e) => app.status_message = Some(format!("Run history unavailable: {:#}", e)),
+    }
+    let key_conflicts = app.config.key_conflicts();
+    if !key_conflicts.is_empty() {
+        app.status_message = Some(key_conflicts.join("; "));
+    }
+    app.daemon = daemon;
+    app.filter_text = filter_text;
+    app.sort_field = sort_field;
+    app.group_filter = cli.group;
+    app.initial_task = cli.task;
     let result = app.run(terminal).await;
     ratatui::restore();
     result
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
 pub enum SortField {
     #[default]
     Id,
@@ -49,13 +160,110 @@ pub enum SortField {
     Path,
 }
 
+/// Where the live log preview of the highlighted task is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PreviewLayout {
+    #[default]
+    Off,
+    /// Below the task table
+    Bottom,
+    /// Right of the task table
+    Right,
+}
+
+impl PreviewLayout {
+    fn next(self) -> Self {
+        match self {
+            PreviewLayout::Off => PreviewLayout::Bottom,
+            PreviewLayout::Bottom => PreviewLayout::Right,
+            PreviewLayout::Right => PreviewLayout::Off,
+        }
+    }
+}
+
+/// Output received by the streaming branch of the main loop
+enum StreamEvent<P> {
+    /// A chunk of one of the log view's streams, or `None` when it ends
+    Log(usize, Result<Option<String>>),
+    /// The preview of the newly highlighted task, and its stream if the task is running
+    PreviewLoaded(Box<LogState>, Option<P>),
+    /// A chunk of the preview's stream, or `None` when it ends
+    Preview(Result<Option<String>>),
+}
+
+/// Loading of a task's log preview, giving the preview and its stream if the task is running
+struct PreviewLoad<P> {
+    task_id: usize,
+    future: std::pin::Pin<Box<dyn Future<Output = (LogState, Option<P>)>>>,
+}
+
+impl<P> std::fmt::Debug for PreviewLoad<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PreviewLoad")
+            .field("task_id", &self.task_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Options for cleaning finished tasks
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CleanOptions {
+    /// Only remove successfully finished tasks
+    pub successful_only: bool,
+    /// Only remove tasks in this group
+    pub group: Option<String>,
+}
+
+/// State of the dependency graph view
+#[derive(Debug)]
+pub struct DepsState {
+    /// Tasks the graph is drawn for
+    pub roots: Vec<usize>,
+    /// Index of the highlighted line
+    pub selected: usize,
+    /// Previous roots, to go back after re-rooting the graph on another task
+    history: Vec<Vec<usize>>,
+    /// The graph's lines, kept until the roots or the state change
+    lines: Vec<deps::DepLine>,
+}
+
+impl DepsState {
+    pub fn new(roots: Vec<usize>, state: Option<&State>) -> Self {
+        let mut deps_state = Self {
+            roots,
+            selected: 0,
+            history: Vec::new(),
+            lines: Vec::new(),
+        };
+        deps_state.update(state);
+        deps_state
+    }
+
+    /// Flatten the graph again, after the roots or the state changed
+    fn update(&mut self, state: Option<&State>) {
+        self.lines = state
+            .map(|s| deps::dependency_lines(s, &self.roots))
+            .unwrap_or_default();
+        self.selected = self.selected.min(self.lines.len().saturating_sub(1));
+    }
+}
+
 #[derive(Debug)]
 enum AppMode {
     Normal,
     Filter,
     Sort,
-    Log(LogState),
+    Log(Box<LogState>),
     Help,
+    /// Picking a group to move the target task(s) to, with the index of the highlighted group
+    MoveGroup(usize),
+    Clean(CleanOptions),
+    ConfirmReset,
+    Deps(DepsState),
+    Env(EnvState),
+    Events(EventsState),
+    /// Run statistics per command, with the index of the highlighted command
+    History(usize),
 }
 
 #[derive(Debug)]
@@ -76,22 +284,55 @@ pub(crate) struct App<P: PueueClientOps> {
     pub(crate) selected_task_ids: HashSet<usize>,
     /// Show details popup
     show_details: bool,
+    /// Scroll offset for details popup
+    details_scroll_offset: u16,
     /// Application mode
     app_mode: AppMode,
     /// Filter text
     filter_text: String,
+    /// Only show tasks in this group
+    pub(crate) group_filter: Option<String>,
+    /// Only show tasks with this status, picked from the summary header
+    pub(crate) status_filter: Option<StatusMatcher>,
+    /// Task to open the log viewer of on start
+    pub(crate) initial_task: Option<usize>,
+    /// Daemon connected to, for the title bar, if it isn't the default one
+    pub(crate) daemon: Option<String>,
     /// Sort field for task table
     pub(crate) sort_field: SortField,
     /// Connection status message for footer (e.g., "Not connected")
     connection_error: Option<String>,
     /// Error modal message (dismissible with Esc)
     error_modal: Option<String>,
+    /// Result of the last action for the footer (cleared on the next key press)
+    pub(crate) status_message: Option<String>,
     /// Application configuration
     pub(crate) config: Config,
     /// Scroll offset for help modal
     help_scroll_offset: u16,
-    /// Streaming client for log mode - separate connection so state polling continues
-    stream_client: Option<P>,
+    /// Streaming clients for log mode, one per streamed task - separate connections so state polling continues
+    stream_clients: Vec<(usize, P)>,
+    preview_layout: PreviewLayout,
+    /// Log preview of the highlighted task
+    pub(crate) preview: Option<LogState>,
+    /// Streaming client for the preview, while the previewed task is running
+    preview_client: Option<P>,
+    /// Preview being loaded. It's kept across loop iterations, so a load that takes longer
+    /// than a tick isn't started over.
+    preview_load: Option<PreviewLoad<P>>,
+    /// Lines the preview pane has room for, as of the last draw
+    preview_height: usize,
+    /// Results of hooks and other background events, for the event panel
+    pub(crate) events: EventLog,
+    /// The configured hooks, compiled when the config is loaded
+    hooks: Vec<hooks::Hook>,
+    /// Hooks running in the background send their results here
+    event_sender: tokio::sync::mpsc::UnboundedSender<events::Event>,
+    event_receiver: tokio::sync::mpsc::UnboundedReceiver<events::Event>,
+    /// Finished runs of tasks, kept across sessions
+    pub(crate) history: Option<History>,
+    /// Daemons that couldn't be reached at the last refresh, by index
+    unreachable: HashSet<usize>,
 }
 
 impl<P: PueueClientOps> App<P> {
@@ -99,6 +340,12 @@ impl<P: PueueClientOps> App<P> {
     pub fn new(pueue_client: P, config: Config) -> Self {
         let mut table_state = TableState::default();
         table_state.select(Some(0));
+        let (event_sender, event_receiver) = tokio::sync::mpsc::unbounded_channel();
+        // Hooks were validated when the config was loaded
+        let (hooks, status_message) = match hooks::compile_hooks(&config.hooks) {
+            Ok(hooks) => (hooks, None),
+            Err(e) => (Vec::new(), Some(format!("Hooks disabled: {:#}", e))),
+        };
 
         Self {
             running: false,
@@ -109,14 +356,31 @@ impl<P: PueueClientOps> App<P> {
             current_task_id: None,
             selected_task_ids: HashSet::new(),
             show_details: false,
+            details_scroll_offset: 0,
             app_mode: AppMode::Normal,
             filter_text: String::new(),
+            group_filter: None,
+            status_filter: None,
+            initial_task: None,
+            daemon: None,
             sort_field: SortField::default(),
             connection_error: None,
             error_modal: None,
+            status_message,
             config,
             help_scroll_offset: 0,
-            stream_client: None,
+            stream_clients: Vec::new(),
+            preview_layout: PreviewLayout::default(),
+            preview: None,
+            preview_client: None,
+            preview_load: None,
+            preview_height: 0,
+            events: EventLog::default(),
+            hooks,
+            event_sender,
+            event_receiver,
+            history: None,
+            unreachable: HashSet::new(),
         }
     }
 
@@ -127,6 +391,10 @@ impl<P: PueueClientOps> App<P> {
         tick_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
         let mut event_stream = EventStream::new();
 
+        if let Some(task_id) = self.initial_task.take() {
+            self.open_initial_task(task_id).await;
+        }
+
         while self.running {
             // Draw the UI
             terminal.draw(|frame| self.draw(frame))?;
@@ -146,47 +414,15 @@ impl<P: PueueClientOps> App<P> {
                     }
                 }
 
-                // Stream logs when in Log mode with an active stream
+                // Stream logs when in Log mode with active streams, or the preview otherwise.
                 // Chunks are produced once every 1000ms by the pueue client
-                chunk_result = async {
-                    if matches!(self.app_mode, AppMode::Log(_))
-                        && let Some(stream_client) = &mut self.stream_client
-                    {
-                        return Some(stream_client.receive_stream_chunk().await);
-                    }
-                    // If not in log mode or no stream, pend forever (let other branches have a turn)
-                    std::future::pending::<Option<Result<Option<String>>>>().await
-                } => {
-                    if let Some(result) = chunk_result
-                        && let AppMode::Log(log_state) = &mut self.app_mode
-                    {
-                        match result {
-                            Ok(Some(chunk)) => {
-                                if !chunk.is_empty() {
-                                    log_state.logs.push_str(&chunk);
-                                    // Update autoscroll if enabled
-                                    if log_state.autoscroll {
-                                        let terminal_size = crossterm::terminal::size()?;
-                                        let page_height = terminal_size.1.saturating_sub(2);
-                                        let page_width = terminal_size.0.saturating_sub(2);
-                                        log_state.update_autoscroll(page_height, page_width);
-                                    }
-                                }
-                            }
-                            Ok(None) => {
-                                // Stream closed (task finished)
-                                self.stream_client = None;
-                            }
-                            Err(_) => {
-                                // Error receiving chunk, close the stream
-                                self.stream_client = None;
-                            }
-                        }
-                    }
+                stream_event = self.next_stream_event() => {
+                    self.on_stream_event(stream_event);
                 }
 
                 // Tick timeout for state refresh
                 _ = tick_interval.tick() => {
+                    self.collect_events();
                     // Fetch pueue state on tick
                     // Show connection errors in footer but keep running
                     match self.refresh_state().await {
@@ -222,6 +458,170 @@ impl<P: PueueClientOps> App<P> {
         Ok(())
     }
 
+    /// Wait for the next chunk of streamed logs.
+    /// The preview of a newly highlighted task is loaded here too, on its own connection, so
+    /// it doesn't hold up state polling and is simply dropped if the cursor moves on.
+    pub(crate) async fn next_stream_event(&mut self) -> StreamEvent<P> {
+        if matches!(self.app_mode, AppMode::Log(_)) {
+            if !self.stream_clients.is_empty() {
+                // Take the first chunk from any of the streamed tasks
+                let receives = self.stream_clients.iter_mut().map(|(task_id, client)| {
+                    Box::pin(async move { (*task_id, client.receive_stream_chunk().await) })
+                });
+                let ((task_id, result), _, _) = futures::future::select_all(receives).await;
+                return StreamEvent::Log(task_id, result);
+            }
+        } else if self.preview_layout != PreviewLayout::Off
+            && let Some(task_id) = self.current_task_id
+        {
+            let is_stashed = self
+                .state
+                .as_ref()
+                .and_then(|s| s.tasks.get(&task_id))
+                .is_some_and(|t| matches!(t.status, TaskStatus::Stashed { .. }));
+            // Reload when the cursor moves, or when a stashed task has been enqueued since
+            let stale = self.preview.as_ref().is_none_or(|preview| {
+                preview.task_id != task_id
+                    || (preview.status.as_deref() == Some("Stashed") && !is_stashed)
+            });
+            if !stale {
+                self.preview_load = None;
+            } else if self
+                .preview_load
+                .as_ref()
+                .is_none_or(|load| load.task_id != task_id)
+            {
+                self.preview_load = Some(self.load_preview(task_id));
+            }
+            if let Some(load) = &mut self.preview_load {
+                let (preview, client) = load.future.as_mut().await;
+                self.preview_load = None;
+                return StreamEvent::PreviewLoaded(Box::new(preview), client);
+            }
+            if let Some(client) = &mut self.preview_client {
+                return StreamEvent::Preview(client.receive_stream_chunk().await);
+            }
+        }
+        // If there's nothing to stream, pend forever (let other branches have a turn)
+        std::future::pending().await
+    }
+
+    pub(crate) fn on_stream_event(&mut self, event: StreamEvent<P>) {
+        match event {
+            StreamEvent::Log(task_id, result) => {
+                let AppMode::Log(log_state) = &mut self.app_mode else {
+                    return;
+                };
+                match result {
+                    Ok(Some(chunk)) => {
+                        log_state.push_streamed(task_id, &chunk, jiff::Timestamp::now());
+                        // Update autoscroll if enabled
+                        if log_state.autoscroll
+                            && let Ok(terminal_size) = crossterm::terminal::size()
+                        {
+                            let page_height = terminal_size.1.saturating_sub(2);
+                            let page_width = terminal_size.0.saturating_sub(2);
+                            log_state.update_autoscroll(page_height, page_width);
+                        }
+                    }
+                    // Stream closed (task finished), or error receiving chunk
+                    Ok(None) | Err(_) => {
+                        self.stream_clients.retain(|(id, _)| *id != task_id);
+                        if self.stream_clients.is_empty() {
+                            log_state.streaming = false;
+                            log_state.status = Some(
+                                if result.is_ok() {
+                                    "Finished"
+                                } else {
+                                    "Stream closed"
+                                }
+                                .to_string(),
+                            );
+                        }
+                    }
+                }
+            }
+            StreamEvent::PreviewLoaded(preview, client) => {
+                self.preview = Some(*preview);
+                self.preview_client = client;
+            }
+            StreamEvent::Preview(result) => {
+                let Some(preview) = &mut self.preview else {
+                    return;
+                };
+                match result {
+                    Ok(Some(chunk)) => {
+                        preview.push_streamed(preview.task_id, &chunk, jiff::Timestamp::now())
+                    }
+                    Ok(None) | Err(_) => {
+                        self.preview_client = None;
+                        preview.streaming = false;
+                        preview.status = Some("Finished".to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Load the log preview of a task: the output so far, only as much as the pane shows,
+    /// and a stream of what follows if the task may produce more
+    fn load_preview(&self, task_id: usize) -> PreviewLoad<P> {
+        let status = self
+            .state
+            .as_ref()
+            .and_then(|s| s.tasks.get(&task_id))
+            .map(|t| t.status.clone());
+
+        let mut preview = LogState::new(vec![task_id]);
+        preview.set_labels(&self.pueue_client.daemon_status());
+        preview.max_lines = self.max_log_lines();
+        preview.set_highlights(self.highlights());
+        preview.status = status.as_ref().map(ui::status_display);
+
+        let lines = Some(self.preview_height.max(1));
+        let connection = self.pueue_client.new(task_id);
+        let future = Box::pin(async move {
+            let result = match status {
+                // Stashed tasks have no output, and the daemon hangs on stream requests for them
+                Some(TaskStatus::Stashed { .. }) | None => Ok(None),
+                Some(TaskStatus::Done { .. }) => match connection.await {
+                    Ok(mut client) => client.get_log(task_id, lines).await.map(|task_log| {
+                        preview.push_str(task_id, &task_log.output);
+                        preview.output_complete = task_log.output_complete;
+                        None
+                    }),
+                    Err(e) => Err(e),
+                },
+                _ => match connection.await {
+                    Ok(mut client) => {
+                        client
+                            .start_log_stream(task_id, lines)
+                            .await
+                            .map(|initial_logs| {
+                                preview.push_str(task_id, &initial_logs);
+                                Some(client)
+                            })
+                    }
+                    Err(e) => Err(e),
+                },
+            };
+
+            match result {
+                Ok(client) => {
+                    preview.streaming = client.is_some();
+                    (preview, client)
+                }
+                Err(e) => {
+                    // Keep the failed preview, so it isn't retried until the cursor moves
+                    preview.streaming = false;
+                    preview.status = Some(format!("Error: {}", e));
+                    (preview, None)
+                }
+            }
+        });
+        PreviewLoad { task_id, future }
+    }
+
     /// Renders the user interface.
     pub(crate) fn draw(&mut self, frame: &mut Frame) {
         let task_ids = self.get_sorted_task_ids(&self.filter_text, self.sort_field);
@@ -229,19 +629,34 @@ impl<P: PueueClientOps> App<P> {
         // Sync table selection with current_task_id
         self.sync_selection_with_task_id(&task_ids);
 
+        // The preview always follows the end of the output
+        if let Some(area) = ui::preview_area(frame.area(), self.preview_layout) {
+            self.preview_height = area.height.saturating_sub(2) as usize;
+            if let Some(preview) = &mut self.preview {
+                preview
+                    .update_autoscroll(area.height.saturating_sub(2), area.width.saturating_sub(2));
+            }
+        }
+
         let log_view = if let AppMode::Log(log_state) = &self.app_mode {
-            Some((log_state.logs.as_str(), log_state.scroll_offset))
+            Some(&**log_state)
         } else {
             None
         };
 
+        let daemons = self.pueue_client.daemon_status();
         let mut ui_state = ui::UiState {
             state: &self.state,
             table_state: &mut self.table_state,
             task_ids: &task_ids,
             now: jiff::Timestamp::now(),
             show_details: self.show_details,
+            details_scroll_offset: self.details_scroll_offset,
             filter_text: &self.filter_text,
+            group_filter: self.group_filter.as_deref(),
+            status_filter: self.status_filter,
+            daemon: self.daemon.as_deref(),
+            daemons: &daemons,
             input_mode: matches!(self.app_mode, AppMode::Filter),
             sort_mode: matches!(self.app_mode, AppMode::Sort),
             sort_field: self.sort_field,
@@ -251,6 +666,48 @@ impl<P: PueueClientOps> App<P> {
             selected_task_ids: &self.selected_task_ids,
             help_mode: matches!(self.app_mode, AppMode::Help),
             help_scroll_offset: self.help_scroll_offset,
+            group_picker: if let AppMode::MoveGroup(selected) = &self.app_mode {
+                Some(*selected)
+            } else {
+                None
+            },
+            clean_options: if let AppMode::Clean(options) = &self.app_mode {
+                Some(options)
+            } else {
+                None
+            },
+            confirm_reset: matches!(self.app_mode, AppMode::ConfirmReset),
+            status_message: self.status_message.as_deref(),
+            deps_view: match &self.app_mode {
+                AppMode::Deps(deps_state) if self.state.is_some() => {
+                    Some((&deps_state.lines, deps_state.selected))
+                }
+                _ => None,
+            },
+            env_view: if let AppMode::Env(env_state) = &self.app_mode {
+                Some(env_state)
+            } else {
+                None
+            },
+            history: self.history.as_ref(),
+            history_view: if let AppMode::History(selected) = &self.app_mode {
+                Some(*selected)
+            } else {
+                None
+            },
+            events_view: if let AppMode::Events(events_state) = &self.app_mode {
+                Some((&self.events, events_state))
+            } else {
+                None
+            },
+            preview: (self.preview_layout != PreviewLayout::Off).then(|| {
+                (
+                    self.preview_layout,
+                    self.preview
+                        .as_ref()
+                        .filter(|p| Some(p.task_id) == self.current_task_id),
+                )
+            }),
             custom_commands: &self.config.custom_commands,
             config_path: self.config.config_path.as_deref(),
         };
@@ -316,6 +773,7 @@ impl<P: PueueClientOps> App<P> {
         terminal: &mut ratatui::Terminal<B>,
     ) -> Result<()> {
         let mut next_mode = None;
+        self.status_message = None;
 
         match &mut self.app_mode {
             AppMode::Filter => match key.code {
@@ -358,10 +816,97 @@ impl<P: PueueClientOps> App<P> {
                 let page_height = terminal_size.1.saturating_sub(2);
                 let page_width = terminal_size.0.saturating_sub(2);
 
-                if key.code == KeyCode::Esc {
-                    // Drop the stream client when exiting log mode
-                    self.stream_client = None;
+                log_state.message = None;
+                if let Some(prompt) = &mut log_state.save_prompt {
+                    match key.code {
+                        KeyCode::Esc => log_state.save_prompt = None,
+                        KeyCode::Enter => {
+                            let prompt = prompt.clone();
+                            let header = prompt
+                                .header
+                                .then(|| {
+                                    self.state
+                                        .as_ref()
+                                        .and_then(|s| s.tasks.get(&log_state.task_id))
+                                        .map(|task| {
+                                            log::log_header(
+                                                &log_state.label(log_state.task_id),
+                                                task,
+                                            )
+                                        })
+                                })
+                                .flatten();
+                            let path = std::path::PathBuf::from(&prompt.path);
+                            log_state.message = Some(
+                                match log_state.save(&path, header.as_deref(), prompt.strip_ansi) {
+                                    Ok(lines) => {
+                                        format!("Saved {} lines to {}", lines, path.display())
+                                    }
+                                    Err(e) => format!("Failed to save log: {}", e),
+                                },
+                            );
+                            log_state.save_prompt = None;
+                        }
+                        KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            prompt.strip_ansi = !prompt.strip_ansi;
+                        }
+                        KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            prompt.header = !prompt.header;
+                        }
+                        KeyCode::Backspace => {
+                            prompt.path.pop();
+                        }
+                        KeyCode::Char(c) => prompt.path.push(c),
+                        _ => {}
+                    }
+                } else if log_state.jump_input.is_some() {
+                    log_state.handle_key(key, page_height, page_width);
+                } else if key.code == KeyCode::Esc {
+                    // Drop the stream clients when exiting log mode
+                    self.stream_clients.clear();
                     next_mode = Some(AppMode::Normal);
+                } else if key.code == KeyCode::Char('v') {
+                    // The scroll position is kept, as the log view is left as is while the viewer runs
+                    let (cmd, file) = log_viewer_command(&self.config, log_state);
+                    let result = match &file {
+                        Ok(_) => exec::run_command(
+                            terminal,
+                            &cmd,
+                            &std::env::current_dir()
+                                .unwrap_or_else(|_| std::path::PathBuf::from("/")),
+                        ),
+                        Err(e) => Err(anyhow::anyhow!("{}", e)),
+                    };
+                    // Deletes the file, now the viewer has exited
+                    drop(file);
+                    if let Err(e) = result {
+                        log_state.message =
+                            Some(format!("Failed to open log in {}: {}", cmd[0], e));
+                    }
+                } else if key.code == KeyCode::Char('y') {
+                    let text = log_state.visible_text(page_height, page_width);
+                    log_state.message =
+                        Some(copy_to_clipboard(&self.config, &text, "the visible lines"));
+                } else if key.code == KeyCode::Char('Y') {
+                    log_state.message = Some(match log_state.match_text() {
+                        Some(text) => copy_to_clipboard(&self.config, &text, "the matching line"),
+                        None => "No match to copy, jump to one with n/N/E first".to_string(),
+                    });
+                } else if key.code == KeyCode::Char('s') {
+                    let task_path = self
+                        .state
+                        .as_ref()
+                        .and_then(|s| s.tasks.get(&log_state.task_id))
+                        .map(|task| task.path.clone())
+                        .unwrap_or_default();
+                    log_state.save_prompt = Some(log::SavePrompt {
+                        path: task_path
+                            .join(format!("pueue-{}.log", log_state.file_label()))
+                            .display()
+                            .to_string(),
+                        strip_ansi: true,
+                        header: false,
+                    });
                 } else {
                     log_state.handle_key(key, page_height, page_width);
                 }
@@ -413,6 +958,191 @@ impl<P: PueueClientOps> App<P> {
                 // don't overscroll into black rows below the help text
                 self.help_scroll_offset = self.help_scroll_offset.min(max_offset);
             }
+            AppMode::Clean(options) => match key.code {
+                KeyCode::Esc => {
+                    next_mode = Some(AppMode::Normal);
+                }
+                KeyCode::Char('q') => self.quit(),
+                KeyCode::Char('s') => {
+                    options.successful_only = !options.successful_only;
+                }
+                KeyCode::Char('g') => {
+                    options.group = if options.group.is_some() {
+                        None
+                    } else {
+                        self.current_task_id
+                            .and_then(|id| self.state.as_ref()?.tasks.get(&id))
+                            .map(|task| task.group.clone())
+                    };
+                }
+                KeyCode::Char('R') => {
+                    next_mode = Some(AppMode::ConfirmReset);
+                }
+                KeyCode::Enter => {
+                    let options = options.clone();
+                    next_mode = Some(AppMode::Normal);
+                    self.clean_tasks(options).await;
+                }
+                _ => {}
+            },
+            AppMode::ConfirmReset => {
+                if key.code == KeyCode::Char('y') {
+                    self.reset_daemon().await;
+                }
+                // Anything other than 'y' cancels
+                next_mode = Some(AppMode::Normal);
+            }
+            AppMode::Deps(deps_state) => {
+                let highlighted = deps_state
+                    .lines
+                    .get(deps_state.selected)
+                    .map(|line| line.task_id);
+
+                match key.code {
+                    KeyCode::Esc | KeyCode::Char('D') => {
+                        next_mode = Some(AppMode::Normal);
+                    }
+                    KeyCode::Char('q') => self.quit(),
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        deps_state.selected = deps_state.selected.saturating_sub(1);
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        deps_state.selected =
+                            (deps_state.selected + 1).min(deps_state.lines.len().saturating_sub(1));
+                    }
+                    KeyCode::Right | KeyCode::Char('l') => {
+                        // Re-root the graph on the highlighted parent or child
+                        if let Some(task_id) = highlighted
+                            && deps_state.roots != [task_id]
+                        {
+                            let previous = std::mem::replace(&mut deps_state.roots, vec![task_id]);
+                            deps_state.history.push(previous);
+                            deps_state.selected = 0;
+                            deps_state.update(self.state.as_ref());
+                        }
+                    }
+                    KeyCode::Left | KeyCode::Char('h') => {
+                        if let Some(previous) = deps_state.history.pop() {
+                            deps_state.roots = previous;
+                            deps_state.selected = 0;
+                            deps_state.update(self.state.as_ref());
+                        }
+                    }
+                    KeyCode::Enter => {
+                        if let Some(task_id) = highlighted {
+                            next_mode = Some(AppMode::Normal);
+                            self.jump_to_task(task_id);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            AppMode::History(selected) => match key.code {
+                KeyCode::Esc | KeyCode::Char('H') => {
+                    next_mode = Some(AppMode::Normal);
+                }
+                KeyCode::Char('q') => self.quit(),
+                KeyCode::Up | KeyCode::Char('k') => {
+                    *selected = selected.saturating_sub(1);
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    let count = self.history.as_ref().map_or(0, |h| h.stats().len());
+                    *selected = (*selected + 1).min(count.saturating_sub(1));
+                }
+                _ => {}
+            },
+            AppMode::Events(events_state) => match key.code {
+                KeyCode::Esc | KeyCode::Char('E') => {
+                    next_mode = Some(AppMode::Normal);
+                }
+                KeyCode::Char('q') => self.quit(),
+                KeyCode::Up | KeyCode::Char('k') => {
+                    events_state.selected = events_state.selected.saturating_sub(1);
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    let count = self.events.filtered(events_state.task_filter).count();
+                    events_state.selected =
+                        (events_state.selected + 1).min(count.saturating_sub(1));
+                }
+                KeyCode::Char('t') => {
+                    // Only show the events of the highlighted event's task, or all events again
+                    if events_state.task_filter.is_some() {
+                        events_state.task_filter = None;
+                        events_state.selected = 0;
+                    } else if let Some(task_id) = self
+                        .events
+                        .filtered(None)
+                        .nth(events_state.selected)
+                        .and_then(|event| event.task_id)
+                    {
+                        events_state.task_filter = Some(task_id);
+                        events_state.selected = 0;
+                    }
+                }
+                KeyCode::Enter => {
+                    let task_id = self
+                        .events
+                        .filtered(events_state.task_filter)
+                        .nth(events_state.selected)
+                        .and_then(|event| event.task_id);
+                    if let Some(task_id) = task_id {
+                        next_mode = Some(AppMode::Normal);
+                        self.jump_to_task(task_id);
+                    }
+                }
+                _ => {}
+            },
+            AppMode::Env(env_state) => {
+                let terminal_size = crossterm::terminal::size()?;
+                let modal_area =
+                    ui::centered_rect(90, 80, Rect::new(0, 0, terminal_size.0, terminal_size.1));
+                // Borders and the header row
+                let page_height = modal_area.height.saturating_sub(3);
+
+                if env_state.input.is_none() && key.code == KeyCode::Esc {
+                    next_mode = Some(AppMode::Normal);
+                } else if env_state.input.is_none() && key.code == KeyCode::Char('q') {
+                    self.quit();
+                } else if env_state.input.is_none()
+                    && env_state.compare.is_none()
+                    && key.code == KeyCode::Char('r')
+                {
+                    let task_id = env_state.task_id;
+                    let original = env_state.original.clone();
+                    let envs = env_state.envs.clone();
+                    next_mode = Some(AppMode::Normal);
+                    self.restart_with_env(task_id, &original, envs).await;
+                } else {
+                    env_state.handle_key(key, page_height);
+                }
+            }
+            AppMode::MoveGroup(selected) => {
+                let groups: Vec<String> = self
+                    .state
+                    .as_ref()
+                    .map(|s| s.groups.keys().cloned().collect())
+                    .unwrap_or_default();
+                match key.code {
+                    KeyCode::Esc => {
+                        next_mode = Some(AppMode::Normal);
+                    }
+                    KeyCode::Char('q') => self.quit(),
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        *selected = selected.saturating_sub(1);
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        *selected = (*selected + 1).min(groups.len().saturating_sub(1));
+                    }
+                    KeyCode::Enter => {
+                        let group = groups.get(*selected).cloned();
+                        next_mode = Some(AppMode::Normal);
+                        if let Some(group) = group {
+                            self.move_tasks_to_group(&group).await;
+                        }
+                    }
+                    _ => {}
+                }
+            }
             AppMode::Normal => {
                 // Handle error modal first - Esc dismisses it
                 if self.error_modal.is_some() {
@@ -422,11 +1152,64 @@ impl<P: PueueClientOps> App<P> {
                         _ => {}
                     }
                 } else if self.show_details {
+                    let terminal_size = crossterm::terminal::size()?;
+                    let modal_area = ui::centered_rect(
+                        60,
+                        60,
+                        Rect::new(0, 0, terminal_size.0, terminal_size.1),
+                    );
+                    let content_height = modal_area.height.saturating_sub(2);
+                    let content_width = modal_area.width.saturating_sub(2).max(1);
+                    let details_text = self
+                        .current_task_id
+                        .zip(self.state.as_ref())
+                        .and_then(|(id, s)| {
+                            s.tasks.get(&id).map(|task| {
+                                ui::build_details_text(
+                                    id,
+                                    task,
+                                    s,
+                                    &self.pueue_client.daemon_status(),
+                                    &jiff::Timestamp::now(),
+                                    self.history.as_ref(),
+                                )
+                            })
+                        })
+                        .unwrap_or_default();
+                    let line_count =
+                        ui::modal_line_count(&details_text, content_width, content_height);
+                    let max_offset = line_count.saturating_sub(content_height);
+
                     match key.code {
-                        KeyCode::Esc => self.show_details = false,
+                        KeyCode::Esc | KeyCode::Char('d') => self.show_details = false,
                         KeyCode::Char('q') => self.quit(),
+                        KeyCode::Up | KeyCode::Char('k') => {
+                            self.details_scroll_offset =
+                                self.details_scroll_offset.saturating_sub(1);
+                        }
+                        KeyCode::Down | KeyCode::Char('j') => {
+                            self.details_scroll_offset =
+                                self.details_scroll_offset.saturating_add(1);
+                        }
+                        KeyCode::PageUp => {
+                            self.details_scroll_offset =
+                                self.details_scroll_offset.saturating_sub(content_height);
+                        }
+                        KeyCode::PageDown => {
+                            self.details_scroll_offset =
+                                self.details_scroll_offset.saturating_add(content_height);
+                        }
+                        KeyCode::Home => {
+                            self.details_scroll_offset = 0;
+                        }
+                        KeyCode::End => {
+                            self.details_scroll_offset = max_offset;
+                        }
                         _ => {}
                     }
+
+                    // don't overscroll into black rows below the details text
+                    self.details_scroll_offset = self.details_scroll_offset.min(max_offset);
                 } else {
                     match key.code {
                         KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => {
@@ -438,8 +1221,24 @@ impl<P: PueueClientOps> App<P> {
                                 self.filter_text.clear();
                             } else if !self.selected_task_ids.is_empty() {
                                 self.selected_task_ids.clear();
+                            } else if self.status_filter.is_some() {
+                                self.status_filter = None;
+                            } else {
+                                self.group_filter = None;
                             }
                         }
+                        KeyCode::Tab => {
+                            self.next_status_filter(true);
+                            self.update_current_task_id();
+                        }
+                        KeyCode::BackTab => {
+                            self.next_status_filter(false);
+                            self.update_current_task_id();
+                        }
+                        KeyCode::Char('>') => {
+                            self.next_group_filter();
+                            self.update_current_task_id();
+                        }
                         KeyCode::Char(' ') => {
                             // Toggle selection of current task
                             if let Some(task_id) = self.current_task_id {
@@ -465,42 +1264,44 @@ impl<P: PueueClientOps> App<P> {
                             self.update_current_task_id();
                         }
                         KeyCode::Enter => {
-                            if let Some(i) = self.table_state.selected() {
-                                let task_ids = self.get_filtered_task_ids();
-                                if let Some(id) = task_ids.get(i) {
-                                    let task_id = *id;
-                                    // Stashed tasks have never run, so there are no logs to stream.
-                                    // The pueue daemon hangs on stream requests for such tasks,
-                                    // so skip the stream and show an empty log view instead.
-                                    let is_stashed = self
-                                        .state
-                                        .as_ref()
-                                        .and_then(|s| s.tasks.get(&task_id))
-                                        .is_some_and(|t| {
-                                            matches!(t.status, TaskStatus::Stashed { .. })
-                                        });
-
-                                    if is_stashed {
-                                        next_mode = Some(AppMode::Log(LogState::new(task_id)));
-                                    } else {
-                                        // Create streaming client and start the stream
-                                        match self.start_log_stream(task_id).await {
-                                            Ok(log_state) => {
-                                                next_mode = Some(AppMode::Log(log_state));
-                                            }
-                                            Err(e) => {
-                                                self.error_modal = Some(format!(
-                                                    "Failed to start log stream: {}",
-                                                    e
-                                                ));
-                                            }
-                                        }
+                            // A multi-selection opens a merged log of all the selected tasks
+                            let task_ids: Vec<usize> = if self.selected_task_ids.len() > 1 {
+                                let mut ids: Vec<usize> =
+                                    self.selected_task_ids.iter().copied().collect();
+                                ids.sort();
+                                ids
+                            } else {
+                                let filtered_ids = self.get_filtered_task_ids();
+                                self.table_state
+                                    .selected()
+                                    .and_then(|i| filtered_ids.get(i).copied())
+                                    .into_iter()
+                                    .collect()
+                            };
+                            if !task_ids.is_empty() {
+                                match self.open_log(task_ids).await {
+                                    Ok(log_state) => {
+                                        next_mode = Some(AppMode::Log(Box::new(log_state)));
+                                    }
+                                    Err(e) => {
+                                        self.stream_clients.clear();
+                                        self.error_modal =
+                                            Some(format!("Failed to load logs: {}", e));
                                     }
                                 }
                             }
                         }
+                        KeyCode::Char('L') => {
+                            self.preview_layout = self.preview_layout.next();
+                            if self.preview_layout == PreviewLayout::Off {
+                                self.preview = None;
+                                self.preview_client = None;
+                                self.preview_load = None;
+                            }
+                        }
                         KeyCode::Char('d') => {
                             if self.table_state.selected().is_some() {
+                                self.details_scroll_offset = 0;
                                 self.show_details = true;
                             }
                         }
@@ -591,6 +1392,24 @@ impl<P: PueueClientOps> App<P> {
                         KeyCode::Char('f') => {
                             self.app_mode = AppMode::Filter;
                         }
+                        KeyCode::Char('y') | KeyCode::Char('Y') => {
+                            let task = self
+                                .current_task_id
+                                .and_then(|id| self.state.as_ref().and_then(|s| s.tasks.get(&id)));
+                            if let Some(task) = task {
+                                let (text, what) = if key.code == KeyCode::Char('y') {
+                                    (task.command.clone(), "command")
+                                } else {
+                                    (task.path.display().to_string(), "path")
+                                };
+                                let message = copy_to_clipboard(
+                                    &self.config,
+                                    &text,
+                                    &format!("the {} of task {}", what, self.task_label(task.id)),
+                                );
+                                self.status_message = Some(message);
+                            }
+                        }
                         KeyCode::Char('s') => {
                             self.app_mode = AppMode::Sort;
                         }
@@ -609,8 +1428,8 @@ impl<P: PueueClientOps> App<P> {
                                         Some(format!("Failed to open config in {}: {}", editor, e));
                                 } else {
                                     // Reload config after editing
-                                    match Config::load() {
-                                        Ok(new_config) => self.config = new_config,
+                                    match Config::load_from_path(config_path) {
+                                        Ok(new_config) => self.set_config(new_config),
                                         Err(e) => {
                                             self.error_modal =
                                                 Some(format!("Failed to reload config: {}", e))
@@ -625,6 +1444,70 @@ impl<P: PueueClientOps> App<P> {
                             self.help_scroll_offset = 0;
                             next_mode = Some(AppMode::Help);
                         }
+                        KeyCode::Char('+') | KeyCode::Char('=') => {
+                            self.change_priority(1).await;
+                        }
+                        KeyCode::Char('-') => {
+                            self.change_priority(-1).await;
+                        }
+                        KeyCode::Char('m') => {
+                            let groups = self.group_names();
+                            if !self.get_action_target_ids().is_empty() && !groups.is_empty() {
+                                // Start on the current task's group
+                                let current_group = self
+                                    .current_task_id
+                                    .and_then(|id| self.state.as_ref()?.tasks.get(&id))
+                                    .map(|task| task.group.clone());
+                                let selected = groups
+                                    .iter()
+                                    .position(|g| Some(g) == current_group.as_ref())
+                                    .unwrap_or(0);
+                                next_mode = Some(AppMode::MoveGroup(selected));
+                            }
+                        }
+                        KeyCode::Char('w') => {
+                            self.switch_selected_tasks().await;
+                        }
+                        KeyCode::Char('C') => {
+                            next_mode = Some(AppMode::Clean(CleanOptions::default()));
+                        }
+                        KeyCode::Char('e') => {
+                            // Diff two selected tasks, otherwise view the current task
+                            let mut selected: Vec<usize> =
+                                self.selected_task_ids.iter().copied().collect();
+                            selected.sort();
+                            let tasks = self.state.as_ref().map(|s| &s.tasks);
+                            let env_state = match (selected.as_slice(), tasks) {
+                                ([a, b], Some(tasks)) => match (tasks.get(a), tasks.get(b)) {
+                                    (Some(ta), Some(tb)) => {
+                                        Some(EnvState::diff(*a, &ta.envs, *b, &tb.envs))
+                                    }
+                                    _ => None,
+                                },
+                                (_, Some(tasks)) => self
+                                    .current_task_id
+                                    .and_then(|id| tasks.get(&id))
+                                    .map(|task| EnvState::new(task.id, &task.envs)),
+                                _ => None,
+                            };
+                            if let Some(env_state) = env_state {
+                                next_mode = Some(AppMode::Env(env_state));
+                            }
+                        }
+                        KeyCode::Char('E') => {
+                            next_mode = Some(AppMode::Events(EventsState::default()));
+                        }
+                        KeyCode::Char('H') => {
+                            next_mode = Some(AppMode::History(0));
+                        }
+                        KeyCode::Char('D') => {
+                            let mut roots = self.get_action_target_ids();
+                            roots.sort();
+                            if !roots.is_empty() {
+                                next_mode =
+                                    Some(AppMode::Deps(DepsState::new(roots, self.state.as_ref())));
+                            }
+                        }
                         KeyCode::Char('r') => {
                             let target_ids = self.get_action_target_ids();
                             if !target_ids.is_empty() {
@@ -803,11 +1686,22 @@ impl<P: PueueClientOps> App<P> {
             .as_ref()
             .map(|s| {
                 let now = jiff::Timestamp::now();
+                let daemons = self.pueue_client.daemon_status();
                 let mut ids: Vec<usize> = s
                     .tasks
                     .iter()
                     .filter(|(id, task)| {
-                        ui::format_task(**id, task, &now).matches_filter(filter_text)
+                        self.group_filter
+                            .as_ref()
+                            .is_none_or(|group| daemons::group_matches(group, &task.group))
+                            && self
+                                .status_filter
+                                .is_none_or(|status| status.matches(&task.status))
+                            && (ui::format_task(**id, task, &now, &daemons)
+                                .matches_filter(filter_text)
+                                || ui::host_name(&daemons, **id).is_some_and(|host| {
+                                    host.to_lowercase().contains(&filter_text.to_lowercase())
+                                }))
                     })
                     .map(|(id, _)| *id)
                     .collect();
@@ -837,43 +1731,631 @@ impl<P: PueueClientOps> App<P> {
             .unwrap_or_default()
     }
 
+    /// Move the table cursor to the given task, clearing the filter if it hides the task
+    pub(crate) fn jump_to_task(&mut self, task_id: usize) {
+        if !self.get_filtered_task_ids().contains(&task_id) {
+            self.filter_text.clear();
+            self.status_filter = None;
+        }
+        if let Some(row) = self
+            .get_filtered_task_ids()
+            .iter()
+            .position(|&id| id == task_id)
+        {
+            self.table_state.select(Some(row));
+            self.current_task_id = Some(task_id);
+        }
+    }
+
+    /// Restart a task with a modified environment.
+    /// Queued and stashed tasks are updated in place. Finished tasks can't have their
+    /// environment changed, so a copy of the task is queued with the new environment.
+    pub(crate) async fn restart_with_env(
+        &mut self,
+        task_id: usize,
+        original: &std::collections::BTreeMap<String, String>,
+        envs: std::collections::BTreeMap<String, String>,
+    ) {
+        let Some(task) = self.state.as_ref().and_then(|s| s.tasks.get(&task_id)) else {
+            return;
+        };
+
+        let result = match &task.status {
+            TaskStatus::Queued { .. } | TaskStatus::Stashed { .. } => {
+                let mut changes: Vec<(String, Option<String>)> = envs
+                    .iter()
+                    .filter(|(k, v)| original.get(*k) != Some(*v))
+                    .map(|(k, v)| (k.clone(), Some(v.clone())))
+                    .collect();
+                changes.extend(
+                    original
+                        .keys()
+                        .filter(|k| !envs.contains_key(*k))
+                        .map(|k| (k.clone(), None)),
+                );
+                let count = changes.len();
+                let mut result = Ok(());
+                for (key, value) in changes {
+                    result = self.pueue_client.set_task_env(task_id, key, value).await;
+                    if result.is_err() {
+                        break;
+                    }
+                }
+                result.map(|()| {
+                    format!(
+                        "Updated {} variable(s) of task {}",
+                        count,
+                        self.task_label(task_id)
+                    )
+                })
+            }
+            TaskStatus::Done { .. } => {
+                let request = AddRequest {
+                    command: task.original_command.clone(),
+                    path: task.path.clone(),
+                    envs: envs.into_iter().collect(),
+                    start_immediately: false,
+                    stashed: false,
+                    group: task.group.clone(),
+                    enqueue_at: None,
+                    dependencies: vec![],
+                    priority: Some(task.priority),
+                    label: task.label.clone(),
+                };
+                self.pueue_client
+                    .add_task(task_id, request)
+                    .await
+                    .map(|new_id| {
+                        format!(
+                            "Queued task {} as task {} with modified environment",
+                            self.task_label(task_id),
+                            self.task_label(new_id)
+                        )
+                    })
+            }
+            _ => Err(anyhow::anyhow!(
+                "only queued, stashed or finished tasks can be restarted with a new environment"
+            )),
+        };
+
+        match result {
+            Ok(message) => {
+                self.status_message = Some(message);
+                let _ = self.refresh_state().await;
+            }
+            Err(e) => {
+                self.error_modal = Some(format!("Failed to restart task with environment: {}", e));
+            }
+        }
+    }
+
+    /// Names of all groups known to the daemon, in display order
+    pub(crate) fn group_names(&self) -> Vec<String> {
+        self.state
+            .as_ref()
+            .map(|s| s.groups.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Raise (positive delta) or lower (negative delta) the priority of the target task(s).
+    /// Only queued and stashed tasks can be edited, so other tasks are skipped.
+    pub(crate) async fn change_priority(&mut self, delta: i32) {
+        let Some(state) = &self.state else {
+            return;
+        };
+        let priorities: Vec<(usize, i32)> = self
+            .get_action_target_ids()
+            .into_iter()
+            .filter_map(|task_id| state.tasks.get(&task_id))
+            .filter(|task| task.is_queued() || task.is_stashed())
+            .map(|task| (task.id, task.priority.saturating_add(delta)))
+            .collect();
+
+        if priorities.is_empty() {
+            return;
+        }
+        if let Err(e) = self.pueue_client.set_priorities(priorities).await {
+            self.error_modal = Some(format!("Failed to change priority: {}", e));
+        } else {
+            let _ = self.refresh_state().await;
+        }
+    }
+
+    /// Swap the queue position of the two selected tasks
+    pub(crate) async fn switch_selected_tasks(&mut self) {
+        let mut ids: Vec<usize> = self.selected_task_ids.iter().copied().collect();
+        if ids.len() != 2 {
+            self.error_modal = Some("Select exactly two tasks to swap".to_string());
+            return;
+        }
+        ids.sort();
+
+        if let Err(e) = self.pueue_client.switch_tasks(ids[0], ids[1]).await {
+            self.error_modal = Some(format!("Failed to swap tasks: {}", e));
+        } else {
+            self.selected_task_ids.clear();
+            let _ = self.refresh_state().await;
+        }
+    }
+
+    /// Move the target task(s) to another group.
+    /// Pueue can't change the group of an existing task, so queued and stashed tasks are
+    /// re-added to the new group and the originals removed. Other tasks are left untouched,
+    /// as are tasks that others wait for: the copy gets a new id, so they'd keep waiting for
+    /// the original, which the daemon then refuses to remove.
+    pub(crate) async fn move_tasks_to_group(&mut self, group: &str) {
+        let Some(state) = &self.state else {
+            return;
+        };
+
+        let mut to_add = Vec::new();
+        let mut skipped = 0;
+        let mut with_dependants = Vec::new();
+        for task_id in self.get_action_target_ids() {
+            let Some(task) = state.tasks.get(&task_id) else {
+                continue;
+            };
+            if task.group == group {
+                continue;
+            }
+            let enqueue_at = match &task.status {
+                TaskStatus::Queued { .. } => None,
+                TaskStatus::Stashed { enqueue_at } => *enqueue_at,
+                _ => {
+                    skipped += 1;
+                    continue;
+                }
+            };
+            let has_dependants = state.tasks.values().any(|other| {
+                other.dependencies.contains(&task_id)
+                    && !matches!(other.status, TaskStatus::Done { .. })
+            });
+            if has_dependants {
+                with_dependants.push(task_id);
+                continue;
+            }
+            to_add.push((
+                task_id,
+                AddRequest {
+                    command: task.original_command.clone(),
+                    path: task.path.clone(),
+                    envs: task.envs.clone(),
+                    start_immediately: false,
+                    stashed: task.is_stashed(),
+                    group: group.to_string(),
+                    enqueue_at,
+                    dependencies: task.dependencies.clone(),
+                    priority: Some(task.priority),
+                    label: task.label.clone(),
+                },
+            ));
+        }
+
+        // A move adds a copy of each task to the new group, then removes the originals
+        let mut moved = Vec::new();
+        for (task_id, request) in to_add {
+            match self.pueue_client.add_task(task_id, request).await {
+                Ok(new_id) => moved.push((task_id, new_id)),
+                Err(e) => {
+                    self.error_modal = Some(format!(
+                        "Failed to move task {}: {}",
+                        self.task_label(task_id),
+                        e
+                    ));
+                    break;
+                }
+            }
+        }
+
+        if !moved.is_empty() {
+            let (old_ids, new_ids): (Vec<usize>, Vec<usize>) = moved.iter().copied().unzip();
+            if let Err(e) = self.pueue_client.remove_tasks(old_ids).await {
+                // Don't leave the tasks in both groups: take the copies out again
+                let copies: Vec<String> = moved
+                    .iter()
+                    .map(|(old_id, new_id)| {
+                        format!(
+                            "{} as {}",
+                            self.task_label(*old_id),
+                            self.task_label(*new_id)
+                        )
+                    })
+                    .collect();
+                self.error_modal = Some(match self.pueue_client.remove_tasks(new_ids).await {
+                    Ok(()) => format!("Failed to move task(s), they were left as they were: {}", e),
+                    Err(undo) => format!(
+                        "Task(s) {} were copied to group {}, but neither the originals ({}) \
+                         nor the copies ({}) could be removed",
+                        copies.join(", "),
+                        group,
+                        e,
+                        undo
+                    ),
+                });
+            }
+            self.selected_task_ids.clear();
+        }
+        let mut not_moved = Vec::new();
+        if skipped > 0 {
+            not_moved.push(format!(
+                "{} task(s) not moved: only queued or stashed tasks can change group",
+                skipped
+            ));
+        }
+        if !with_dependants.is_empty() {
+            with_dependants.sort();
+            let ids: Vec<String> = with_dependants
+                .iter()
+                .map(|id| self.task_label(*id))
+                .collect();
+            not_moved.push(format!(
+                "Task(s) {} not moved: other tasks depend on them",
+                ids.join(", ")
+            ));
+        }
+        if !not_moved.is_empty() && self.error_modal.is_none() {
+            self.error_modal = Some(not_moved.join("\n"));
+        }
+
+        let _ = self.refresh_state().await;
+        self.update_current_task_id();
+    }
+
+    /// Remove finished tasks (like `pueue clean`) and report how many were removed
+    pub(crate) async fn clean_tasks(&mut self, options: CleanOptions) {
+        // The daemon doesn't report what it removed, so note the matching tasks beforehand,
+        // in a fresh state as the last refresh may be a tick old
+        let _ = self.refresh_state().await;
+        let cleanable: Vec<usize> = self.state.as_ref().map_or(Vec::new(), |s| {
+            s.tasks
+                .values()
+                .filter(|task| match &task.status {
+                    TaskStatus::Done { result, .. } => {
+                        !options.successful_only || matches!(result, TaskResult::Success)
+                    }
+                    _ => false,
+                })
+                .filter(|task| options.group.as_ref().is_none_or(|g| &task.group == g))
+                .map(|task| task.id)
+                .collect()
+        });
+
+        // With several daemons, those that could be reached are cleaned even if one failed
+        let result = self
+            .pueue_client
+            .clean_tasks(options.successful_only, options.group)
+            .await;
+        self.selected_task_ids.clear();
+        let _ = self.refresh_state().await;
+        self.update_current_task_id();
+        let count = self.state.as_ref().map_or(0, |s| {
+            cleanable
+                .iter()
+                .filter(|id| !s.tasks.contains_key(id))
+                .count()
+        });
+        self.status_message = Some(format!("Cleaned {} finished task(s)", count));
+        if let Err(e) = result {
+            self.error_modal = Some(format!("Failed to clean tasks: {}", e));
+        }
+    }
+
+    /// Kill and remove all tasks, and report how many were affected
+    pub(crate) async fn reset_daemon(&mut self) {
+        let task_ids: Vec<usize> = self
+            .state
+            .as_ref()
+            .map_or(Vec::new(), |s| s.tasks.keys().copied().collect());
+
+        // Like cleaning, a daemon that fails doesn't stop the others being reset
+        let result = self.pueue_client.reset().await;
+        self.selected_task_ids.clear();
+        let _ = self.refresh_state().await;
+        self.update_current_task_id();
+        let count = self.state.as_ref().map_or(0, |s| {
+            task_ids
+                .iter()
+                .filter(|id| !s.tasks.contains_key(id))
+                .count()
+        });
+        self.status_message = Some(format!("Reset daemon, removed {} task(s)", count));
+        if let Err(e) = result {
+            self.error_modal = Some(format!("Failed to reset daemon: {}", e));
+        }
+    }
+
     /// Refresh the state immediately from the pueue client
     async fn refresh_state(&mut self) -> Result<()> {
-        let new_state = self.pueue_client.get_state().await?;
+        let daemon_count = self.pueue_client.daemon_status().len().max(1);
+        let new_state = match self.pueue_client.get_state().await {
+            Ok(state) => state,
+            Err(e) => {
+                self.unreachable.extend(0..daemon_count);
+                return Err(e);
+            }
+        };
+        if let Some(history) = &mut self.history
+            && let Err(e) = history.record(&new_state, &self.pueue_client.daemon_status())
+        {
+            self.status_message = Some(format!("{:#}", e));
+        }
+        let daemons = self.pueue_client.daemon_status();
+        let reachable = |index: usize| daemons.get(index).is_none_or(|d| d.error.is_none());
+        let reconnected: HashSet<usize> = self
+            .unreachable
+            .iter()
+            .copied()
+            .filter(|index| reachable(*index))
+            .collect();
+        self.unreachable = (0..daemon_count)
+            .filter(|index| !reachable(*index))
+            .collect();
+        if let Some(mut old_state) = self.state.take() {
+            // The first state of a daemon after it's (re)connected is taken as is, rather
+            // than its tasks being seen as added or changed
+            old_state
+                .tasks
+                .retain(|id, _| !reconnected.contains(&daemons::daemon_index(*id, daemon_count)));
+            for (id, task) in &new_state.tasks {
+                if reconnected.contains(&daemons::daemon_index(*id, daemon_count)) {
+                    old_state.tasks.insert(*id, task.clone());
+                }
+            }
+            self.record_changes(&old_state, &new_state);
+            self.notify_finished(&old_state, &new_state);
+            self.run_hooks(&old_state, &new_state);
+        }
         self.state = Some(new_state);
+        if let AppMode::Deps(deps_state) = &mut self.app_mode {
+            deps_state.update(self.state.as_ref());
+        }
         Ok(())
     }
 
+    /// Add the tasks added, removed or changing status between two refreshes to the event log
+    fn record_changes(&mut self, old_state: &State, new_state: &State) {
+        let daemons = self.pueue_client.daemon_status();
+        let unreachable = |id: usize| {
+            daemons
+                .get(daemons::daemon_index(id, daemons.len()))
+                .is_some_and(|daemon| daemon.error.is_some())
+        };
+        let now = jiff::Timestamp::now();
+        for event in events::task_changes(old_state, new_state, now, unreachable) {
+            self.events.push(event);
+        }
+    }
+
+    /// Notify about the tasks that finished between two refreshes, if configured to
+    fn notify_finished(&mut self, old_state: &State, new_state: &State) {
+        let Some(config) = &self.config.notifications else {
+            return;
+        };
+        let daemons = self.pueue_client.daemon_status();
+        for id in notify::finished_tasks(old_state, new_state) {
+            let task = &new_state.tasks[&id];
+            if !notify::wanted(config, task) {
+                continue;
+            }
+            let notification = notify::Notification::new(id, task, &daemons);
+            if let Err(e) = notify::send(config, &notification) {
+                self.status_message = Some(format!("Failed to notify: {}", e));
+            }
+        }
+    }
+
+    /// Move the results of hooks that finished since the last tick to the event panel
+    pub(crate) fn collect_events(&mut self) {
+        while let Ok(event) = self.event_receiver.try_recv() {
+            self.events.push(event);
+        }
+    }
+
+    /// Only show the tasks of the status after (or before) the current one in the summary
+    /// header, or all tasks after the last one
+    fn next_status_filter(&mut self, forward: bool) {
+        let statuses = ui::SUMMARY_STATUSES;
+        let position = self
+            .status_filter
+            .and_then(|current| statuses.iter().position(|status| *status == current));
+        self.status_filter = match (position, forward) {
+            (None, true) => statuses.first().copied(),
+            (None, false) => statuses.last().copied(),
+            (Some(i), true) => statuses.get(i + 1).copied(),
+            (Some(i), false) => i.checked_sub(1).map(|i| statuses[i]),
+        };
+    }
+
+    /// Only show the group after the current one, or all groups after the last one
+    fn next_group_filter(&mut self) {
+        let Some(state) = &self.state else {
+            return;
+        };
+        let mut groups = state.groups.keys();
+        self.group_filter = match &self.group_filter {
+            None => groups.next().cloned(),
+            Some(current) => groups.skip_while(|name| *name != current).nth(1).cloned(),
+        };
+    }
+
+    /// Use a reloaded config, with its hooks compiled once for every refresh to come, and
+    /// warn about custom commands that won't run
+    fn set_config(&mut self, config: Config) {
+        match hooks::compile_hooks(&config.hooks) {
+            Ok(hooks) => self.hooks = hooks,
+            Err(e) => {
+                self.hooks.clear();
+                self.status_message = Some(format!("Hooks disabled: {:#}", e));
+            }
+        }
+        let key_conflicts = config.key_conflicts();
+        if !key_conflicts.is_empty() {
+            self.status_message = Some(key_conflicts.join("; "));
+        }
+        self.config = config;
+    }
+
+    /// Start the configured hooks of the tasks that changed status between two refreshes
+    fn run_hooks(&mut self, old_state: &State, new_state: &State) {
+        if self.hooks.is_empty() {
+            return;
+        }
+        let changed = events::status_changes(old_state, new_state);
+        if changed.is_empty() {
+            return;
+        }
+        let daemons = self.pueue_client.daemon_status();
+        let now = jiff::Timestamp::now();
+        for id in changed {
+            let (old, new) = (&old_state.tasks[&id], &new_state.tasks[&id]);
+            for hook in self.hooks.iter().filter(|hook| hook.matches(old, new)) {
+                let input = hooks::HookInput {
+                    previous_status: ui::status_display(&old.status),
+                    task: ui::TaskJson::new(id, new, &now, &daemons),
+                };
+                let input = serde_json::to_string(&input).unwrap_or_default();
+                hooks::spawn(
+                    id,
+                    hook.command_line(id, new, &daemons),
+                    Some(new.path.clone()),
+                    input,
+                    self.event_sender.clone(),
+                );
+            }
+        }
+    }
+
+    /// The task table as plain text or JSON, filtered and sorted as it would be in the UI
+    pub(crate) async fn list(
+        &mut self,
+        filter_text: &str,
+        sort_field: SortField,
+        json: bool,
+    ) -> Result<String> {
+        self.refresh_state().await?;
+        let task_ids = self.get_sorted_task_ids(filter_text, sort_field);
+        let Some(state) = &self.state else {
+            return Ok(String::new());
+        };
+        let now = jiff::Timestamp::now();
+        let daemons = self.pueue_client.daemon_status();
+        if json {
+            Ok(ui::task_list_json(state, &task_ids, &now, &daemons)? + "\n")
+        } else {
+            Ok(ui::task_list_text(state, &task_ids, &now, &daemons))
+        }
+    }
+
     /// Set running to false to quit the application.
     fn quit(&mut self) {
         self.running = false;
     }
 
-    /// Create a new streaming client and start the log stream.
-    /// Stores the streaming client in `self.stream_client`.
-    async fn start_log_stream(&mut self, task_id: usize) -> Result<LogState> {
-        let mut stream_client = self.pueue_client.new().await?;
-        let initial_logs = stream_client.start_log_stream(task_id, None).await?;
+    /// Open the log viewer of the task given on the command line, with the cursor on it
+    pub(crate) async fn open_initial_task(&mut self, task_id: usize) {
+        if let Err(e) = self.refresh_state().await {
+            self.connection_error = Some(e.to_string());
+            return;
+        }
+        let Some(task) = self.state.as_ref().and_then(|s| s.tasks.get(&task_id)) else {
+            self.error_modal = Some(format!("Task {} not found", task_id));
+            return;
+        };
+        // `--group` was asked for too, so keep it rather than showing another group's task
+        if let Some(group) = &self.group_filter
+            && !daemons::group_matches(group, &task.group)
+        {
+            self.error_modal = Some(format!(
+                "Task {} is in group {}, not {}",
+                task_id, task.group, group
+            ));
+            return;
+        }
+        self.jump_to_task(task_id);
+        match self.open_log(vec![task_id]).await {
+            Ok(log_state) => self.app_mode = AppMode::Log(Box::new(log_state)),
+            Err(e) => {
+                self.stream_clients.clear();
+                self.error_modal = Some(format!("Failed to load logs: {}", e));
+            }
+        }
+    }
 
-        self.stream_client = Some(stream_client);
+    /// Open a log view of one or more tasks.
+    /// Finished tasks' output won't change, so it's fetched in one go. Other tasks are
+    /// streamed, each on its own connection as the daemon only streams one task at a time.
+    /// Streaming clients are stored in `self.stream_clients`.
+    async fn open_log(&mut self, task_ids: Vec<usize>) -> Result<LogState> {
+        let mut log_state = LogState::new(task_ids.clone());
+        log_state.set_labels(&self.pueue_client.daemon_status());
+        log_state.max_lines = self.max_log_lines();
+        log_state.set_highlights(self.highlights());
 
-        let mut log_state = LogState {
-            task_id,
-            logs: initial_logs,
-            scroll_offset: 0,
-            autoscroll: true,
-        };
+        for task_id in task_ids {
+            let status = self
+                .state
+                .as_ref()
+                .and_then(|s| s.tasks.get(&task_id))
+                .map(|t| t.status.clone());
+            match status {
+                // Stashed tasks have never run, so there are no logs.
+                // The pueue daemon hangs on stream requests for such tasks, so skip them.
+                Some(TaskStatus::Stashed { .. }) => {}
+                Some(TaskStatus::Done { .. }) => {
+                    let task_log = self
+                        .pueue_client
+                        .get_log(task_id, self.config.log_lines)
+                        .await?;
+                    log_state.push_str(task_id, &task_log.output);
+                    log_state.output_complete &= task_log.output_complete;
+                }
+                _ => {
+                    let mut stream_client = self.pueue_client.new(task_id).await?;
+                    let initial_logs = stream_client.start_log_stream(task_id, None).await?;
+                    log_state.push_str(task_id, &initial_logs);
+                    self.stream_clients.push((task_id, stream_client));
+                }
+            }
+        }
 
-        // Scroll to end of initial logs
-        if let Ok(terminal_size) = crossterm::terminal::size() {
-            let page_height = terminal_size.1.saturating_sub(2);
-            let page_width = terminal_size.0.saturating_sub(2);
-            log_state.update_autoscroll(page_height, page_width);
+        log_state.streaming = !self.stream_clients.is_empty();
+        if let [task_id] = log_state.task_ids[..] {
+            let status = self
+                .state
+                .as_ref()
+                .and_then(|s| s.tasks.get(&task_id))
+                .map(|task| &task.status);
+            log_state.status = status.map(ui::status_display);
+            // Point straight at the failure when opening a failed task's log
+            if let Some(TaskStatus::Done { result, .. }) = status
+                && !matches!(result, TaskResult::Success)
+                && let Some(line_number) = log_state.first_error_line()
+            {
+                log_state.message =
+                    Some(format!("E: jump to the first error (line {})", line_number));
+            }
         }
+        scroll_to_end(&mut log_state);
 
         Ok(log_state)
     }
 
+    /// A task's id as shown to the user, see [`ui::task_label`]
+    fn task_label(&self, id: usize) -> String {
+        ui::task_label(&self.pueue_client.daemon_status(), id)
+    }
+
+    /// Highlight rules for logs, validated when the config was loaded
+    fn highlights(&self) -> Vec<crate::log::Highlight> {
+        crate::log::compile_highlights(&self.config.highlight_rules()).unwrap_or_default()
+    }
+
+    fn max_log_lines(&self) -> usize {
+        self.config
+            .log_max_lines
+            .unwrap_or(crate::log::DEFAULT_MAX_LINES)
+    }
+
     /// Get the path of the currently selected task
     fn get_current_task_path(&self) -> Option<std::path::PathBuf> {
         let task_id = self.current_task_id?;
@@ -895,96 +2377,33 @@ impl<P: PueueClientOps> App<P> {
     }
 }
 
-pub struct LogState {
-    pub task_id: usize,
-    pub logs: String,
-    pub scroll_offset: u16,
-    pub autoscroll: bool,
-}
-
-impl std::fmt::Debug for LogState {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("LogState")
-            .field("task_id", &self.task_id)
-            .field("logs", &format!("({} bytes)", self.logs.len()))
-            .field("scroll_offset", &self.scroll_offset)
-            .field("autoscroll", &self.autoscroll)
-            .finish()
+/// Copy text to the clipboard, returning a message saying what was copied or why it failed
+fn copy_to_clipboard(config: &Config, text: &str, what: &str) -> String {
+    match clipboard::copy(text, config.clipboard_command.as_deref()) {
+        Ok(()) => format!("Copied {}", what),
+        Err(e) => format!("Failed to copy {}: {}", what, e),
     }
 }
 
-impl LogState {
-    pub fn new(task_id: usize) -> Self {
-        Self {
-            task_id,
-            logs: String::new(),
-            scroll_offset: 0,
-            autoscroll: true,
-        }
-    }
-
-    pub(crate) fn handle_key(&mut self, key: KeyEvent, page_height: u16, page_width: u16) -> bool {
-        match key.code {
-            KeyCode::Char('j') | KeyCode::Down => {
-                self.scroll_offset = self.scroll_offset.saturating_add(1);
-                self.autoscroll = false;
-            }
-            KeyCode::Char('k') | KeyCode::Up => {
-                self.scroll_offset = self.scroll_offset.saturating_sub(1);
-                self.autoscroll = false;
-            }
-            KeyCode::PageUp | KeyCode::Char('b') => {
-                self.scroll_offset = self.scroll_offset.saturating_sub(page_height);
-                self.autoscroll = false;
-            }
-            KeyCode::PageDown | KeyCode::Char(' ') => {
-                self.scroll_offset = self.scroll_offset.saturating_add(page_height);
-                self.autoscroll = false;
-            }
-            KeyCode::Home | KeyCode::Char('g') => {
-                self.scroll_offset = 0;
-                self.autoscroll = false;
-            }
-            KeyCode::End | KeyCode::Char('G') => {
-                self.autoscroll = true;
-                self.update_autoscroll(page_height, page_width);
-            }
-            KeyCode::Char('d') => {
-                self.scroll_offset = self.scroll_offset.saturating_add(page_height / 2);
-                self.autoscroll = false;
-            }
-            KeyCode::Char('u') => {
-                self.scroll_offset = self.scroll_offset.saturating_sub(page_height / 2);
-                self.autoscroll = false;
-            }
-            _ => return false,
-        }
-
-        // Clamp manual scrolling to the last possible offset, so we can't overscroll into blank space.
-        if !self.autoscroll {
-            let max_offset = self
-                .visual_line_count(page_width)
-                .saturating_sub(page_height);
-            self.scroll_offset = self.scroll_offset.min(max_offset);
-        }
-        true
-    }
-
-    fn visual_line_count(&self, page_width: u16) -> u16 {
-        // Use ratatui's own wrapping algorithm (Paragraph::line_count) so our autoscroll matches
-        // exactly what gets rendered.
-        use ratatui::widgets::{Paragraph, Wrap};
-
-        let width = page_width.max(1);
-        let logs = self.logs.replace('\t', "        ");
-        let p = Paragraph::new(logs).wrap(Wrap { trim: false });
-        p.line_count(width) as u16
+/// The command to open a log in the configured viewer, and the temporary file it's given.
+/// The viewer is only run if the file could be written.
+pub(crate) fn log_viewer_command(
+    config: &Config,
+    log_state: &LogState,
+) -> (Vec<String>, std::io::Result<tempfile::NamedTempFile>) {
+    let mut cmd = config.log_viewer_command();
+    let file = log_state.save_temporary();
+    if let Ok(file) = &file {
+        cmd.push(file.path().display().to_string());
     }
+    (cmd, file)
+}
 
-    pub(crate) fn update_autoscroll(&mut self, page_height: u16, page_width: u16) {
-        if self.autoscroll {
-            let lines = self.visual_line_count(page_width);
-            self.scroll_offset = lines.saturating_sub(page_height);
-        }
+/// Scroll a new log view to the end of its initial content
+fn scroll_to_end(log_state: &mut LogState) {
+    if let Ok(terminal_size) = crossterm::terminal::size() {
+        let page_height = terminal_size.1.saturating_sub(2);
+        let page_width = terminal_size.0.saturating_sub(2);
+        log_state.update_autoscroll(page_height, page_width);
     }
 }
@@ -0,0 +1,114 @@
+use crate::config::{NotificationConfig, NotifyMethod, ResultFilter};
+use crate::daemons::DaemonStatus;
+use anyhow::{Context, Result};
+use pueue_lib::state::State;
+use pueue_lib::task::{Task, TaskResult, TaskStatus};
+use std::io::Write;
+
+/// A task finishing, as told to the user
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub title: String,
+    pub message: String,
+}
+
+impl Notification {
+    /// e.g. "Task 3 on build: Failed (1)", with the task's command as the message
+    pub fn new(id: usize, task: &Task, daemons: &[DaemonStatus]) -> Self {
+        let host = crate::ui::host_name(daemons, id)
+            .map(|host| format!(" on {}", host))
+            .unwrap_or_default();
+        Notification {
+            title: format!(
+                "Task {}{}: {}",
+                crate::daemons::local_id(id, daemons.len()),
+                host,
+                crate::ui::status_display(&task.status)
+            ),
+            message: task.command.clone(),
+        }
+    }
+}
+
+/// Ids of the tasks that were in the old state and have finished since
+pub fn finished_tasks(old: &State, new: &State) -> Vec<usize> {
+    crate::events::status_changes(old, new)
+        .into_iter()
+        .filter(|id| matches!(new.tasks[id].status, TaskStatus::Done { .. }))
+        .collect()
+}
+
+/// Whether the config asks to be told about this task
+pub fn wanted(config: &NotificationConfig, task: &Task) -> bool {
+    let TaskStatus::Done { result, .. } = &task.status else {
+        return false;
+    };
+    let result_matches = config.results.is_empty()
+        || config.results.iter().any(|filter| match filter {
+            ResultFilter::Success => matches!(result, TaskResult::Success),
+            ResultFilter::Failure => !matches!(result, TaskResult::Success),
+        });
+    let label_matches = config.labels.is_empty()
+        || task
+            .label
+            .as_ref()
+            .is_some_and(|label| config.labels.contains(label));
+    (config.groups.is_empty()
+        || config
+            .groups
+            .iter()
+            .any(|group| crate::daemons::group_matches(group, &task.group)))
+        && label_matches
+        && result_matches
+}
+
+/// The escape sequence for a notification, None for methods that don't write to the terminal
+pub fn sequence(method: NotifyMethod, notification: &Notification, tmux: bool) -> Option<String> {
+    // Control characters in the command would end the sequence early
+    let clean = |text: &str| text.replace(|c: char| c.is_control(), " ");
+    let sequence = match method {
+        NotifyMethod::Bell => return Some("\x07".to_string()),
+        NotifyMethod::Osc9 => format!(
+            "\x1b]9;{}: {}\x07",
+            clean(&notification.title),
+            clean(&notification.message)
+        ),
+        NotifyMethod::Osc777 => format!(
+            "\x1b]777;notify;{};{}\x07",
+            clean(&notification.title).replace(';', ","),
+            clean(&notification.message)
+        ),
+        NotifyMethod::Command => return None,
+    };
+    Some(crate::clipboard::terminal_sequence(sequence, tmux))
+}
+
+/// Send a notification the configured way. A notifier command is left running in the
+/// background, so a slow one doesn't hold up the UI.
+pub fn send(config: &NotificationConfig, notification: &Notification) -> Result<()> {
+    let tmux = std::env::var_os("TMUX").is_some();
+    match sequence(config.method, notification, tmux) {
+        Some(sequence) => {
+            let mut stdout = std::io::stdout();
+            stdout.write_all(sequence.as_bytes())?;
+            stdout.flush()?;
+        }
+        None => {
+            let cmd = config
+                .command
+                .as_deref()
+                .filter(|cmd| !cmd.is_empty())
+                .context("No notification command configured")?;
+            tokio::process::Command::new(&cmd[0])
+                .args(&cmd[1..])
+                .arg(&notification.title)
+                .arg(&notification.message)
+                .stdin(std::process::Stdio::null())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .spawn()
+                .with_context(|| format!("Failed to run {}", cmd[0]))?;
+        }
+    }
+    Ok(())
+}
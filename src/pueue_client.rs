@@ -5,10 +5,15 @@ use pueue_lib::network::socket::ConnectionSettings;
 use pueue_lib::secret::read_shared_secret;
 use pueue_lib::settings::Settings;
 use pueue_lib::state::State;
+use std::path::PathBuf;
 
-pub(crate) trait PueueClientOps: Sized {
-    /// Create a new, independent client connection (e.g. for streaming).
-    async fn new(&self) -> Result<Self>;
+pub(crate) trait PueueClientOps: Sized + 'static {
+    /// Create a new, independent client connection for requests about one task (e.g. for
+    /// streaming its log). When monitoring several daemons, only the task's is connected to.
+    /// The connection doesn't borrow this client, so it can be kept in flight while this
+    /// client is used for other requests.
+    #[allow(clippy::wrong_self_convention)]
+    fn new(&self, task_id: usize) -> impl Future<Output = Result<Self>> + 'static;
     async fn get_state(&mut self) -> Result<State>;
     async fn start_tasks(&mut self, ids: Vec<usize>) -> Result<()>;
     async fn restart_tasks(&mut self, tasks: Vec<TaskToRestart>) -> Result<()>;
@@ -16,6 +21,26 @@ pub(crate) trait PueueClientOps: Sized {
     async fn pause_tasks(&mut self, ids: Vec<usize>) -> Result<()>;
     async fn kill_tasks(&mut self, ids: Vec<usize>) -> Result<()>;
     async fn remove_tasks(&mut self, ids: Vec<usize>) -> Result<()>;
+    /// Set the priority of each given task, using pueue's edit protocol.
+    async fn set_priorities(&mut self, priorities: Vec<(usize, i32)>) -> Result<()>;
+    /// Switch the queue position of two queued or stashed tasks.
+    async fn switch_tasks(&mut self, task_id_1: usize, task_id_2: usize) -> Result<()>;
+    /// Add a new task derived from the `origin` task, which decides the daemon it's added to
+    /// when monitoring several. Returns the id of the new task.
+    async fn add_task(&mut self, origin: usize, request: AddRequest) -> Result<usize>;
+    /// Remove finished tasks, optionally only successful ones and/or only those in a group.
+    async fn clean_tasks(&mut self, successful_only: bool, group: Option<String>) -> Result<()>;
+    /// Kill all tasks, remove them and reset the daemon's state.
+    async fn reset(&mut self) -> Result<()>;
+    /// Set (or with `None`, unset) an environment variable of a queued or stashed task.
+    async fn set_task_env(
+        &mut self,
+        task_id: usize,
+        key: String,
+        value: Option<String>,
+    ) -> Result<()>;
+    /// Fetch the output of a task in one go, optionally only its last `lines` lines.
+    async fn get_log(&mut self, id: usize, lines: Option<usize>) -> Result<TaskLog>;
     /// Start streaming logs for a task. Returns the initial log content.
     /// Use `receive_stream_chunk` to get subsequent chunks.
     async fn start_log_stream(&mut self, id: usize, lines: Option<usize>) -> Result<String>;
@@ -23,16 +48,63 @@ pub(crate) trait PueueClientOps: Sized {
     async fn receive_stream_chunk(&mut self) -> Result<Option<String>>;
     /// Reconnect to the pueue daemon.
     async fn reconnect(&mut self) -> Result<()>;
+    /// Connection status of each daemon, when monitoring several.
+    fn daemon_status(&self) -> Vec<crate::daemons::DaemonStatus> {
+        Vec::new()
+    }
+}
+
+/// Output of a task, as returned by a log request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskLog {
+    pub output: String,
+    /// False if the daemon only sent the last lines of the output
+    pub output_complete: bool,
+}
+
+/// Decompress task output sent by the daemon.
+/// Output is snappy-compressed, except for the plain text error the daemon sends
+/// when it can't read a log file.
+pub fn decompress_log(bytes: &[u8]) -> String {
+    use std::io::Read;
+
+    let mut decompressed = Vec::new();
+    match snap::read::FrameDecoder::new(bytes).read_to_end(&mut decompressed) {
+        Ok(_) => String::from_utf8_lossy(&decompressed).into_owned(),
+        Err(_) => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+/// Which pueue daemon to connect to: the one in pueue's config file (default or given),
+/// optionally using one of the file's profiles
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DaemonTarget {
+    pub config: Option<PathBuf>,
+    pub profile: Option<String>,
+}
+
+impl DaemonTarget {
+    pub fn is_default(&self) -> bool {
+        self.config.is_none() && self.profile.is_none()
+    }
 }
 
 #[derive(Debug)]
 pub struct PueueClient {
-    client: Client,
+    /// None until connected, for daemons that were unreachable at startup
+    client: Option<Client>,
+    target: DaemonTarget,
+    /// Address of the daemon, for display
+    address: String,
 }
 
 impl PueueClient {
-    pub async fn new() -> Result<Self> {
-        let (settings, _) = Settings::read(&None)?;
+    pub async fn new(target: DaemonTarget) -> Result<Self> {
+        let (mut settings, _) = Settings::read(&target.config)?;
+        if let Some(profile) = &target.profile {
+            settings.load_profile(profile)?;
+        }
+        let address = daemon_address(&settings.shared);
         let secret = read_shared_secret(&settings.shared.shared_secret_path())
             .map_err(|e| anyhow!("Failed to read shared secret: {:?}", e))?;
 
@@ -45,22 +117,64 @@ impl PueueClient {
             .await
             .map_err(|e| anyhow!("{:?}", e))?;
 
-        Ok(Self { client })
+        Ok(Self {
+            client: Some(client),
+            target,
+            address,
+        })
+    }
+
+    /// A client that isn't connected yet, which connects on `reconnect`
+    pub fn offline(target: DaemonTarget) -> Self {
+        Self {
+            client: None,
+            target,
+            address: String::new(),
+        }
+    }
+
+    fn client(&mut self) -> Result<&mut Client> {
+        self.client
+            .as_mut()
+            .ok_or_else(|| anyhow!("Not connected to the pueue daemon"))
+    }
+
+    /// The daemon connected to, if it isn't the default one (eg: "work: tcp://build:6924")
+    pub fn description(&self) -> Option<String> {
+        if self.target.is_default() {
+            return None;
+        }
+        Some(match &self.target.profile {
+            Some(profile) => format!("{}: {}", profile, self.address),
+            None => self.address.clone(),
+        })
     }
 }
 
+/// Where the daemon listens, as a unix socket path or TCP host and port
+pub(crate) fn daemon_address(shared: &pueue_lib::settings::Shared) -> String {
+    #[cfg(not(target_os = "windows"))]
+    if shared.use_unix_socket {
+        return match shared.unix_socket_path() {
+            Ok(path) => format!("unix:{}", path.display()),
+            Err(_) => "unix socket".to_string(),
+        };
+    }
+    format!("tcp://{}:{}", shared.host, shared.port)
+}
+
 impl PueueClientOps for PueueClient {
-    async fn new(&self) -> Result<Self> {
-        PueueClient::new().await
+    fn new(&self, _task_id: usize) -> impl Future<Output = Result<Self>> + 'static {
+        PueueClient::new(self.target.clone())
     }
 
     async fn get_state(&mut self) -> Result<State> {
-        self.client
+        self.client()?
             .send_request(Request::Status)
             .await
             .map_err(|e| anyhow!("{:?}", e))?;
         let response = self
-            .client
+            .client()?
             .receive_response()
             .await
             .map_err(|e| anyhow!("{:?}", e))?;
@@ -76,14 +190,14 @@ impl PueueClientOps for PueueClient {
     }
 
     async fn start_tasks(&mut self, ids: Vec<usize>) -> Result<()> {
-        self.client
+        self.client()?
             .send_request(Request::Start(StartRequest {
                 tasks: TaskSelection::TaskIds(ids),
             }))
             .await
             .map_err(|e| anyhow!("{:?}", e))?;
         let _ = self
-            .client
+            .client()?
             .receive_response()
             .await
             .map_err(|e| anyhow!("{:?}", e))?;
@@ -91,7 +205,7 @@ impl PueueClientOps for PueueClient {
     }
 
     async fn restart_tasks(&mut self, tasks: Vec<TaskToRestart>) -> Result<()> {
-        self.client
+        self.client()?
             .send_request(Request::Restart(RestartRequest {
                 tasks,
                 start_immediately: true,
@@ -100,7 +214,7 @@ impl PueueClientOps for PueueClient {
             .await
             .map_err(|e| anyhow!("{:?}", e))?;
         let _ = self
-            .client
+            .client()?
             .receive_response()
             .await
             .map_err(|e| anyhow!("{:?}", e))?;
@@ -108,7 +222,7 @@ impl PueueClientOps for PueueClient {
     }
 
     async fn enqueue_tasks(&mut self, ids: Vec<usize>) -> Result<()> {
-        self.client
+        self.client()?
             .send_request(Request::Enqueue(EnqueueRequest {
                 tasks: TaskSelection::TaskIds(ids),
                 enqueue_at: None,
@@ -116,7 +230,7 @@ impl PueueClientOps for PueueClient {
             .await
             .map_err(|e| anyhow!("{:?}", e))?;
         let _ = self
-            .client
+            .client()?
             .receive_response()
             .await
             .map_err(|e| anyhow!("{:?}", e))?;
@@ -124,7 +238,7 @@ impl PueueClientOps for PueueClient {
     }
 
     async fn pause_tasks(&mut self, ids: Vec<usize>) -> Result<()> {
-        self.client
+        self.client()?
             .send_request(Request::Pause(PauseRequest {
                 tasks: TaskSelection::TaskIds(ids),
                 wait: false,
@@ -132,7 +246,7 @@ impl PueueClientOps for PueueClient {
             .await
             .map_err(|e| anyhow!("{:?}", e))?;
         let _ = self
-            .client
+            .client()?
             .receive_response()
             .await
             .map_err(|e| anyhow!("{:?}", e))?;
@@ -140,7 +254,7 @@ impl PueueClientOps for PueueClient {
     }
 
     async fn kill_tasks(&mut self, ids: Vec<usize>) -> Result<()> {
-        self.client
+        self.client()?
             .send_request(Request::Kill(KillRequest {
                 tasks: TaskSelection::TaskIds(ids),
                 signal: None,
@@ -148,7 +262,7 @@ impl PueueClientOps for PueueClient {
             .await
             .map_err(|e| anyhow!("{:?}", e))?;
         let _ = self
-            .client
+            .client()?
             .receive_response()
             .await
             .map_err(|e| anyhow!("{:?}", e))?;
@@ -156,20 +270,220 @@ impl PueueClientOps for PueueClient {
     }
 
     async fn remove_tasks(&mut self, ids: Vec<usize>) -> Result<()> {
-        self.client
+        self.client()?
             .send_request(Request::Remove(ids))
             .await
             .map_err(|e| anyhow!("{:?}", e))?;
         let _ = self
-            .client
+            .client()?
             .receive_response()
             .await
             .map_err(|e| anyhow!("{:?}", e))?;
         Ok(())
     }
 
+    async fn set_priorities(&mut self, priorities: Vec<(usize, i32)>) -> Result<()> {
+        let ids: Vec<usize> = priorities.iter().map(|(id, _)| *id).collect();
+
+        // Editing is a three-step protocol: lock the tasks and fetch their editable details,
+        // then send back the edited details (or restore the tasks if something went wrong).
+        self.client()?
+            .send_request(Request::EditRequest(ids.clone()))
+            .await
+            .map_err(|e| anyhow!("{:?}", e))?;
+        let response = self
+            .client()?
+            .receive_response()
+            .await
+            .map_err(|e| anyhow!("{:?}", e))?;
+
+        let mut editable_tasks = match response {
+            Response::Edit(editable_tasks) => editable_tasks,
+            Response::Failure(msg) => return Err(anyhow!("Edit request failed: {}", msg)),
+            _ => {
+                self.client()?
+                    .send_request(Request::EditRestore(ids))
+                    .await
+                    .map_err(|e| anyhow!("{:?}", e))?;
+                let _ = self.client()?.receive_response().await;
+                return Err(anyhow!(
+                    "Unexpected response from pueue daemon: {:?}",
+                    response
+                ));
+            }
+        };
+
+        for editable_task in &mut editable_tasks {
+            if let Some((_, priority)) = priorities.iter().find(|(id, _)| *id == editable_task.id) {
+                editable_task.priority = *priority;
+            }
+        }
+
+        self.client()?
+            .send_request(Request::EditedTasks(editable_tasks))
+            .await
+            .map_err(|e| anyhow!("{:?}", e))?;
+        let response = self
+            .client()?
+            .receive_response()
+            .await
+            .map_err(|e| anyhow!("{:?}", e))?;
+
+        match response {
+            Response::Failure(msg) => Err(anyhow!("Edit failed: {}", msg)),
+            _ => Ok(()),
+        }
+    }
+
+    async fn switch_tasks(&mut self, task_id_1: usize, task_id_2: usize) -> Result<()> {
+        self.client()?
+            .send_request(Request::Switch(SwitchRequest {
+                task_id_1,
+                task_id_2,
+            }))
+            .await
+            .map_err(|e| anyhow!("{:?}", e))?;
+        let response = self
+            .client()?
+            .receive_response()
+            .await
+            .map_err(|e| anyhow!("{:?}", e))?;
+
+        match response {
+            Response::Failure(msg) => Err(anyhow!("Switch failed: {}", msg)),
+            _ => Ok(()),
+        }
+    }
+
+    async fn add_task(&mut self, _origin: usize, request: AddRequest) -> Result<usize> {
+        self.client()?
+            .send_request(Request::Add(request))
+            .await
+            .map_err(|e| anyhow!("{:?}", e))?;
+        let response = self
+            .client()?
+            .receive_response()
+            .await
+            .map_err(|e| anyhow!("{:?}", e))?;
+
+        match response {
+            Response::AddedTask(added) => Ok(added.task_id),
+            Response::Failure(msg) => Err(anyhow!("Add failed: {}", msg)),
+            _ => Err(anyhow!(
+                "Unexpected response from pueue daemon: {:?}",
+                response
+            )),
+        }
+    }
+
+    async fn clean_tasks(&mut self, successful_only: bool, group: Option<String>) -> Result<()> {
+        self.client()?
+            .send_request(Request::Clean(CleanRequest {
+                successful_only,
+                group,
+            }))
+            .await
+            .map_err(|e| anyhow!("{:?}", e))?;
+        let response = self
+            .client()?
+            .receive_response()
+            .await
+            .map_err(|e| anyhow!("{:?}", e))?;
+
+        match response {
+            Response::Failure(msg) => Err(anyhow!("Clean failed: {}", msg)),
+            _ => Ok(()),
+        }
+    }
+
+    async fn reset(&mut self) -> Result<()> {
+        self.client()?
+            .send_request(Request::Reset(ResetRequest {
+                target: ResetTarget::All,
+            }))
+            .await
+            .map_err(|e| anyhow!("{:?}", e))?;
+        let response = self
+            .client()?
+            .receive_response()
+            .await
+            .map_err(|e| anyhow!("{:?}", e))?;
+
+        match response {
+            Response::Failure(msg) => Err(anyhow!("Reset failed: {}", msg)),
+            _ => Ok(()),
+        }
+    }
+
+    async fn set_task_env(
+        &mut self,
+        task_id: usize,
+        key: String,
+        value: Option<String>,
+    ) -> Result<()> {
+        let request = match value {
+            Some(value) => EnvRequest::Set {
+                task_id,
+                key,
+                value,
+            },
+            None => EnvRequest::Unset { task_id, key },
+        };
+        self.client()?
+            .send_request(Request::Env(request))
+            .await
+            .map_err(|e| anyhow!("{:?}", e))?;
+        let response = self
+            .client()?
+            .receive_response()
+            .await
+            .map_err(|e| anyhow!("{:?}", e))?;
+
+        match response {
+            Response::Failure(msg) => Err(anyhow!("Env request failed: {}", msg)),
+            _ => Ok(()),
+        }
+    }
+
+    async fn get_log(&mut self, id: usize, lines: Option<usize>) -> Result<TaskLog> {
+        self.client()?
+            .send_request(Request::Log(LogRequest {
+                tasks: TaskSelection::TaskIds(vec![id]),
+                send_logs: true,
+                lines,
+            }))
+            .await
+            .map_err(|e| anyhow!("Failed to send log request: {:?}", e))?;
+        let response = self
+            .client()?
+            .receive_response()
+            .await
+            .map_err(|e| anyhow!("Failed to receive log response: {:?}", e))?;
+
+        match response {
+            Response::Log(mut logs) => {
+                let log = logs
+                    .remove(&id)
+                    .ok_or_else(|| anyhow!("No log for task {}", id))?;
+                Ok(TaskLog {
+                    output: log
+                        .output
+                        .as_deref()
+                        .map(decompress_log)
+                        .unwrap_or_default(),
+                    output_complete: log.output_complete,
+                })
+            }
+            Response::Failure(msg) => Err(anyhow!("Log request failed: {}", msg)),
+            _ => Err(anyhow!(
+                "Unexpected response from pueue daemon: {:?}",
+                response
+            )),
+        }
+    }
+
     async fn start_log_stream(&mut self, id: usize, lines: Option<usize>) -> Result<String> {
-        self.client
+        self.client()?
             .send_request(Request::Stream(StreamRequest {
                 tasks: TaskSelection::TaskIds(vec![id]),
                 lines,
@@ -179,7 +493,7 @@ impl PueueClientOps for PueueClient {
 
         // First response contains the initial log content
         let response = self
-            .client
+            .client()?
             .receive_response()
             .await
             .map_err(|e| anyhow!("Failed to receive stream response: {:?}", e))?;
@@ -198,7 +512,7 @@ impl PueueClientOps for PueueClient {
 
     async fn receive_stream_chunk(&mut self) -> Result<Option<String>> {
         let response = self
-            .client
+            .client()?
             .receive_response()
             .await
             .map_err(|e| anyhow!("Failed to receive stream chunk: {:?}", e))?;
@@ -219,7 +533,7 @@ impl PueueClientOps for PueueClient {
     }
 
     async fn reconnect(&mut self) -> Result<()> {
-        let new_self = Self::new().await?;
+        let new_self = Self::new(self.target.clone()).await?;
         self.client = new_self.client;
         Ok(())
     }
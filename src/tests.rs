@@ -3,19 +3,29 @@ use chrono::{Local, TimeZone};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use pueue_lib::state::State;
 use pueue_lib::task::{Task, TaskResult, TaskStatus};
-use ratatui::{Terminal, backend::TestBackend, buffer::Buffer, layout::Rect, widgets::TableState};
+use ratatui::{
+    Terminal, backend::TestBackend, buffer::Buffer, layout::Rect, style::Color, text::Line,
+    widgets::TableState,
+};
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::PathBuf;
 
-use crate::LogState;
-use crate::SortField;
+use crate::CleanOptions;
+use crate::clipboard;
 use crate::config::Config;
-use crate::pueue_client::PueueClientOps;
+use crate::log::{self, LogState};
+use crate::pueue_client::{self, PueueClientOps, TaskLog, decompress_log};
 use crate::ui;
-use pueue_lib::message::TaskToRestart;
+use crate::{PreviewLayout, SortField};
+use pueue_lib::message::{AddRequest, TaskToRestart};
+use pueue_lib::state::{Group, GroupStatus};
 
 pub struct MockPueueClient {
     state: State,
+    /// How long fetching a log takes, to test slow daemons
+    log_delay: std::time::Duration,
+    /// Whether the daemon can't be reached, shared with the clients made from this one
+    offline: std::sync::Arc<std::sync::atomic::AtomicBool>,
 }
 
 impl MockPueueClient {
@@ -84,23 +94,33 @@ impl MockPueueClient {
         state.tasks.insert(1, task2);
         state.tasks.insert(2, task3);
 
-        Self { state }
+        Self {
+            state,
+            log_delay: std::time::Duration::ZERO,
+            offline: Default::default(),
+        }
     }
 }
 
 impl PueueClientOps for MockPueueClient {
-    async fn new(&self) -> Result<Self> {
+    fn new(&self, _task_id: usize) -> impl Future<Output = Result<Self>> + 'static {
         // We copy state so the streaming client sees the same tasks (e.g. stashed)
         // as the original client. This perverts the interface — ideally `new` wouldn't
         // take `&self`, but without a mock pueue server the streaming client has no
         // other way to know about the current task state. If we mock the server in
         // the future we could remove the `&self` parameter from the trait.
-        Ok(Self {
+        let client = Self {
             state: self.state.clone(),
-        })
+            log_delay: self.log_delay,
+            offline: self.offline.clone(),
+        };
+        async move { Ok(client) }
     }
 
     async fn get_state(&mut self) -> Result<State> {
+        if self.offline.load(std::sync::atomic::Ordering::Relaxed) {
+            return Err(anyhow::anyhow!("Connection refused"));
+        }
         Ok(self.state.clone())
     }
 
@@ -124,17 +144,118 @@ impl PueueClientOps for MockPueueClient {
         Ok(())
     }
 
-    async fn remove_tasks(&mut self, _ids: Vec<usize>) -> Result<()> {
+    async fn remove_tasks(&mut self, ids: Vec<usize>) -> Result<()> {
+        // Like the daemon, refuse to remove running tasks
+        if let Some(id) = ids.iter().find(|id| {
+            self.state
+                .tasks
+                .get(id)
+                .is_some_and(|task| matches!(task.status, TaskStatus::Running { .. }))
+        }) {
+            return Err(anyhow::anyhow!("Task {} is running", id));
+        }
+        for id in ids {
+            self.state.tasks.remove(&id);
+        }
+        Ok(())
+    }
+
+    async fn set_priorities(&mut self, priorities: Vec<(usize, i32)>) -> Result<()> {
+        for (id, priority) in priorities {
+            if let Some(task) = self.state.tasks.get_mut(&id) {
+                task.priority = priority;
+            }
+        }
+        Ok(())
+    }
+
+    async fn switch_tasks(&mut self, task_id_1: usize, task_id_2: usize) -> Result<()> {
+        // Like the daemon, swap the tasks' ids so they trade places in the queue
+        let mut task_1 = self.state.tasks.remove(&task_id_1).unwrap();
+        let mut task_2 = self.state.tasks.remove(&task_id_2).unwrap();
+        task_1.id = task_id_2;
+        task_2.id = task_id_1;
+        self.state.tasks.insert(task_id_2, task_1);
+        self.state.tasks.insert(task_id_1, task_2);
+        Ok(())
+    }
+
+    async fn clean_tasks(&mut self, successful_only: bool, group: Option<String>) -> Result<()> {
+        self.state.tasks.retain(|_, task| {
+            let cleanable = match &task.status {
+                TaskStatus::Done { result, .. } => {
+                    !successful_only || matches!(result, TaskResult::Success)
+                }
+                _ => false,
+            };
+            let in_group = group.as_ref().is_none_or(|g| &task.group == g);
+            !(cleanable && in_group)
+        });
+        Ok(())
+    }
+
+    async fn reset(&mut self) -> Result<()> {
+        self.state.tasks.clear();
+        Ok(())
+    }
+
+    async fn set_task_env(
+        &mut self,
+        task_id: usize,
+        key: String,
+        value: Option<String>,
+    ) -> Result<()> {
+        let task = self.state.tasks.get_mut(&task_id).unwrap();
+        match value {
+            Some(value) => task.envs.insert(key, value),
+            None => task.envs.remove(&key),
+        };
         Ok(())
     }
 
+    async fn add_task(&mut self, _origin: usize, request: AddRequest) -> Result<usize> {
+        let now = Local.timestamp_opt(1767225600, 0).unwrap();
+        let status = if request.stashed {
+            TaskStatus::Stashed {
+                enqueue_at: request.enqueue_at,
+            }
+        } else {
+            TaskStatus::Queued { enqueued_at: now }
+        };
+        let task = Task {
+            id: 0,
+            created_at: now,
+            original_command: request.command.clone(),
+            command: request.command,
+            path: request.path,
+            envs: request.envs,
+            group: request.group,
+            dependencies: request.dependencies,
+            priority: request.priority.unwrap_or(0),
+            label: request.label,
+            status,
+        };
+        Ok(self.state.add_task(task))
+    }
+
+    async fn get_log(&mut self, id: usize, lines: Option<usize>) -> Result<TaskLog> {
+        tokio::time::sleep(self.log_delay).await;
+        let output = format!("Full log of task {}\nLine 2\nLine 3", id);
+        let output_lines: Vec<&str> = output.lines().collect();
+        let skip = lines.map_or(0, |n| output_lines.len().saturating_sub(n));
+        Ok(TaskLog {
+            output: output_lines[skip..].join("\n"),
+            output_complete: skip == 0,
+        })
+    }
+
     async fn start_log_stream(&mut self, id: usize, _lines: Option<usize>) -> Result<String> {
         // Simulate real pueue daemon: hangs forever for stashed tasks (no log file exists)
-        if let Some(task) = self.state.tasks.get(&id) {
-            if matches!(task.status, TaskStatus::Stashed { .. }) {
-                std::future::pending::<()>().await;
-                unreachable!();
-            }
+        if let Some(task) = self.state.tasks.get(&id)
+            && matches!(task.status, TaskStatus::Stashed { .. })
+        {
+            std::future::pending::<()>().await;
+            unreachable!();
         }
         Ok("Log line 1\nLog line 2\nLog line 3".to_string())
     }
@@ -187,9 +308,14 @@ async fn test_ui_snapshot() -> Result<()> {
             state: &Some(state),
             table_state: &mut table_state,
             task_ids: &task_ids,
-            now: jiff_now.clone(),
+            now: jiff_now,
             show_details: false,
+            details_scroll_offset: 0,
             filter_text: "",
+            group_filter: None,
+            status_filter: None,
+            daemon: None,
+            daemons: &[],
             input_mode: false,
             sort_mode: false,
             sort_field: SortField::default(),
@@ -199,6 +325,16 @@ async fn test_ui_snapshot() -> Result<()> {
             selected_task_ids: &HashSet::new(),
             help_mode: false,
             help_scroll_offset: 0,
+            group_picker: None,
+            clean_options: None,
+            confirm_reset: false,
+            status_message: None,
+            deps_view: None,
+            env_view: None,
+            history: None,
+            history_view: None,
+            events_view: None,
+            preview: None,
             custom_commands: &BTreeMap::new(),
             config_path: None,
         };
@@ -233,7 +369,12 @@ async fn test_ui_snapshot_with_details() -> Result<()> {
             task_ids: &task_ids,
             now: jiff_now,
             show_details: true,
+            details_scroll_offset: 0,
             filter_text: "",
+            group_filter: None,
+            status_filter: None,
+            daemon: None,
+            daemons: &[],
             input_mode: false,
             sort_mode: false,
             sort_field: SortField::default(),
@@ -243,6 +384,16 @@ async fn test_ui_snapshot_with_details() -> Result<()> {
             selected_task_ids: &HashSet::new(),
             help_mode: false,
             help_scroll_offset: 0,
+            group_picker: None,
+            clean_options: None,
+            confirm_reset: false,
+            status_message: None,
+            deps_view: None,
+            env_view: None,
+            history: None,
+            history_view: None,
+            events_view: None,
+            preview: None,
             custom_commands: &BTreeMap::new(),
             config_path: None,
         };
@@ -298,9 +449,14 @@ async fn test_ui_snapshot_with_scrollbar() -> Result<()> {
             state: &Some(state),
             table_state: &mut table_state,
             task_ids: &task_ids,
-            now: jiff_now.clone(),
+            now: jiff_now,
             show_details: false,
+            details_scroll_offset: 0,
             filter_text: "",
+            group_filter: None,
+            status_filter: None,
+            daemon: None,
+            daemons: &[],
             input_mode: false,
             sort_mode: false,
             sort_field: SortField::default(),
@@ -310,6 +466,16 @@ async fn test_ui_snapshot_with_scrollbar() -> Result<()> {
             selected_task_ids: &HashSet::new(),
             help_mode: false,
             help_scroll_offset: 0,
+            group_picker: None,
+            clean_options: None,
+            confirm_reset: false,
+            status_message: None,
+            deps_view: None,
+            env_view: None,
+            history: None,
+            history_view: None,
+            events_view: None,
+            preview: None,
             custom_commands: &BTreeMap::new(),
             config_path: None,
         };
@@ -333,7 +499,7 @@ async fn test_ui_snapshot_filter_active() -> Result<()> {
     let task_ids: Vec<usize> = state
         .tasks
         .iter()
-        .filter(|(id, task)| ui::format_task(**id, task, &jiff_now).matches_filter("1"))
+        .filter(|(id, task)| ui::format_task(**id, task, &jiff_now, &[]).matches_filter("1"))
         .map(|(id, _)| *id)
         .collect();
 
@@ -345,7 +511,12 @@ async fn test_ui_snapshot_filter_active() -> Result<()> {
             task_ids: &task_ids,
             now: jiff_now,
             show_details: false,
+            details_scroll_offset: 0,
             filter_text: "1",
+            group_filter: None,
+            status_filter: None,
+            daemon: None,
+            daemons: &[],
             input_mode: false,
             sort_mode: false,
             sort_field: SortField::default(),
@@ -355,6 +526,16 @@ async fn test_ui_snapshot_filter_active() -> Result<()> {
             selected_task_ids: &HashSet::new(),
             help_mode: false,
             help_scroll_offset: 0,
+            group_picker: None,
+            clean_options: None,
+            confirm_reset: false,
+            status_message: None,
+            deps_view: None,
+            env_view: None,
+            history: None,
+            history_view: None,
+            events_view: None,
+            preview: None,
             custom_commands: &BTreeMap::new(),
             config_path: None,
         };
@@ -386,7 +567,12 @@ async fn test_ui_snapshot_remove_task() -> Result<()> {
             task_ids: &task_ids,
             now: jiff_now,
             show_details: false,
+            details_scroll_offset: 0,
             filter_text: "",
+            group_filter: None,
+            status_filter: None,
+            daemon: None,
+            daemons: &[],
             input_mode: false,
             sort_mode: false,
             sort_field: SortField::default(),
@@ -396,6 +582,16 @@ async fn test_ui_snapshot_remove_task() -> Result<()> {
             selected_task_ids: &HashSet::new(),
             help_mode: false,
             help_scroll_offset: 0,
+            group_picker: None,
+            clean_options: None,
+            confirm_reset: false,
+            status_message: None,
+            deps_view: None,
+            env_view: None,
+            history: None,
+            history_view: None,
+            events_view: None,
+            preview: None,
             custom_commands: &BTreeMap::new(),
             config_path: None,
         };
@@ -421,7 +617,8 @@ async fn test_ui_snapshot_log_view() -> Result<()> {
                 \t- Duis aute irure dolor in reprehenderit in voluptate velit esse cillum dolore eu fugiat nulla pariatur.\n\
                 Excepteur sint occaecat cupidatat non proident, sunt in culpa qui officia deserunt mollit anim id est laborum.\n\
                 Long line: Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam, quis nostrud exercitation ullamco laboris nisi ut aliquip ex ea commodo consequat. Duis aute irure dolor in reprehenderit in voluptate velit esse cillum dolore eu fugiat nulla pariatur.";
-    let scroll_offset = 0;
+    let mut log_state = LogState::new(vec![0]);
+    log_state.push_str(0, logs);
 
     terminal.draw(|f| {
         let mut ui_state = ui::UiState {
@@ -430,16 +627,31 @@ async fn test_ui_snapshot_log_view() -> Result<()> {
             task_ids: &task_ids,
             now: jiff_now,
             show_details: false,
+            details_scroll_offset: 0,
             filter_text: "",
+            group_filter: None,
+            status_filter: None,
+            daemon: None,
+            daemons: &[],
             input_mode: false,
             sort_mode: false,
             sort_field: SortField::default(),
-            log_view: Some((logs, scroll_offset)),
+            log_view: Some(&log_state),
             connection_error: None,
             error_modal: None,
             selected_task_ids: &HashSet::new(),
             help_mode: false,
             help_scroll_offset: 0,
+            group_picker: None,
+            clean_options: None,
+            confirm_reset: false,
+            status_message: None,
+            deps_view: None,
+            env_view: None,
+            history: None,
+            history_view: None,
+            events_view: None,
+            preview: None,
             custom_commands: &BTreeMap::new(),
             config_path: None,
         };
@@ -465,16 +677,19 @@ async fn test_ui_snapshot_log_view_end_key() -> Result<()> {
     let mut table_state = TableState::default();
     table_state.select(Some(0));
 
-    let mut log_state = LogState::new(0);
-    log_state.logs = [
-        // A long line that will be wrapped. At width>=6, Ratatui and textwrap disagree on
-        // wrapped row count, so a textwrap-based "End" scroll doesn't reach the end of the logs.
-        "PATH  /very/long/path/preceded/with/spaces/to/align/columns",
-        // Final marker line we must be able to reach with End/G
-        // Keep the marker <= page_width so it doesn't wrap (makes the assertion simple).
-        "ZZ",
-    ]
-    .join("\n");
+    let mut log_state = LogState::new(vec![0]);
+    log_state.push_str(
+        0,
+        &[
+            // A long line that will be wrapped. At width>=6, Ratatui and textwrap disagree on
+            // wrapped row count, so a textwrap-based "End" scroll doesn't reach the end of the logs.
+            "PATH  /very/long/path/preceded/with/spaces/to/align/columns",
+            // Final marker line we must be able to reach with End/G
+            // Keep the marker <= page_width so it doesn't wrap (makes the assertion simple).
+            "ZZ",
+        ]
+        .join("\n"),
+    );
 
     // Simulate 'G' / End key press.
     // Borders take 2 lines => 2 lines of content visible and 40 columns of content width for wrapping.
@@ -494,16 +709,31 @@ async fn test_ui_snapshot_log_view_end_key() -> Result<()> {
             task_ids: &[],
             now: jiff::Timestamp::now(),
             show_details: false,
+            details_scroll_offset: 0,
             filter_text: "",
+            group_filter: None,
+            status_filter: None,
+            daemon: None,
+            daemons: &[],
             input_mode: false,
             sort_mode: false,
             sort_field: SortField::default(),
-            log_view: Some((&log_state.logs, log_state.scroll_offset)),
+            log_view: Some(&log_state),
             connection_error: None,
             error_modal: None,
             selected_task_ids: &HashSet::new(),
             help_mode: false,
             help_scroll_offset: 0,
+            group_picker: None,
+            clean_options: None,
+            confirm_reset: false,
+            status_message: None,
+            deps_view: None,
+            env_view: None,
+            history: None,
+            history_view: None,
+            events_view: None,
+            preview: None,
             custom_commands: &BTreeMap::new(),
             config_path: None,
         };
@@ -542,8 +772,8 @@ async fn test_ui_snapshot_log_view_end_key_then_down() -> Result<()> {
     let mut table_state = TableState::default();
     table_state.select(Some(0));
 
-    let mut log_state = LogState::new(0);
-    log_state.logs = ["AA", "ZZ"].join("\n");
+    let mut log_state = LogState::new(vec![0]);
+    log_state.push_str(0, &["AA", "ZZ"].join("\n"));
 
     // Borders take 2 lines => 2 lines of content visible and 40 columns of content width for wrapping.
     let page_height = 4 - 2;
@@ -572,16 +802,31 @@ async fn test_ui_snapshot_log_view_end_key_then_down() -> Result<()> {
             task_ids: &[],
             now: jiff::Timestamp::now(),
             show_details: false,
+            details_scroll_offset: 0,
             filter_text: "",
+            group_filter: None,
+            status_filter: None,
+            daemon: None,
+            daemons: &[],
             input_mode: false,
             sort_mode: false,
             sort_field: SortField::default(),
-            log_view: Some((&log_state.logs, log_state.scroll_offset)),
+            log_view: Some(&log_state),
             connection_error: None,
             error_modal: None,
             selected_task_ids: &HashSet::new(),
             help_mode: false,
             help_scroll_offset: 0,
+            group_picker: None,
+            clean_options: None,
+            confirm_reset: false,
+            status_message: None,
+            deps_view: None,
+            env_view: None,
+            history: None,
+            history_view: None,
+            events_view: None,
+            preview: None,
             custom_commands: &BTreeMap::new(),
             config_path: None,
         };
@@ -641,6 +886,8 @@ async fn test_log_view_stashed_task_shows_empty() -> Result<()> {
 
     let mock_client = MockPueueClient {
         state: state.clone(),
+        log_delay: std::time::Duration::ZERO,
+        offline: Default::default(),
     };
     let mut app = App::new(mock_client, Config::default());
     app.state = Some(state);
@@ -731,6 +978,8 @@ async fn test_selection_follows_task_id_after_sort() -> Result<()> {
 
     let mock_client = MockPueueClient {
         state: state.clone(),
+        log_delay: std::time::Duration::ZERO,
+        offline: Default::default(),
     };
     let mut app = App::new(mock_client, Config::default());
     app.state = Some(state);
@@ -797,6 +1046,8 @@ async fn test_selection_after_deleting_last_task() -> Result<()> {
 
     let mock_client = MockPueueClient {
         state: state.clone(),
+        log_delay: std::time::Duration::ZERO,
+        offline: Default::default(),
     };
     let mut app = App::new(mock_client, Config::default());
     app.state = Some(state);
@@ -885,7 +1136,12 @@ async fn test_ui_snapshot_multiselect() -> Result<()> {
             task_ids: &task_ids,
             now: jiff_now,
             show_details: false,
+            details_scroll_offset: 0,
             filter_text: "",
+            group_filter: None,
+            status_filter: None,
+            daemon: None,
+            daemons: &[],
             input_mode: false,
             sort_mode: false,
             sort_field: SortField::default(),
@@ -895,6 +1151,16 @@ async fn test_ui_snapshot_multiselect() -> Result<()> {
             selected_task_ids: &selected_task_ids,
             help_mode: false,
             help_scroll_offset: 0,
+            group_picker: None,
+            clean_options: None,
+            confirm_reset: false,
+            status_message: None,
+            deps_view: None,
+            env_view: None,
+            history: None,
+            history_view: None,
+            events_view: None,
+            preview: None,
             custom_commands: &BTreeMap::new(),
             config_path: None,
         };
@@ -936,6 +1202,8 @@ async fn test_select_all_selects_all_tasks() -> Result<()> {
 
     let mock_client = MockPueueClient {
         state: state.clone(),
+        log_delay: std::time::Duration::ZERO,
+        offline: Default::default(),
     };
     let mut app = App::new(mock_client, Config::default());
     app.state = Some(state);
@@ -1032,6 +1300,8 @@ async fn test_select_all_filtered_only_affects_visible() -> Result<()> {
 
     let mock_client = MockPueueClient {
         state: state.clone(),
+        log_delay: std::time::Duration::ZERO,
+        offline: Default::default(),
     };
     let mut app = App::new(mock_client, Config::default());
     app.state = Some(state);
@@ -1190,7 +1460,12 @@ async fn test_ui_snapshot_help_mode() -> Result<()> {
             task_ids: &task_ids,
             now: jiff_now,
             show_details: false,
+            details_scroll_offset: 0,
             filter_text: "",
+            group_filter: None,
+            status_filter: None,
+            daemon: None,
+            daemons: &[],
             input_mode: false,
             sort_mode: false,
             sort_field: SortField::default(),
@@ -1200,6 +1475,16 @@ async fn test_ui_snapshot_help_mode() -> Result<()> {
             selected_task_ids: &HashSet::new(),
             help_mode: true,
             help_scroll_offset: 0,
+            group_picker: None,
+            clean_options: None,
+            confirm_reset: false,
+            status_message: None,
+            deps_view: None,
+            env_view: None,
+            history: None,
+            history_view: None,
+            events_view: None,
+            preview: None,
             custom_commands: &custom_commands,
             config_path: Some(std::path::Path::new("/home/user/.config/pui/config.toml")),
         };
@@ -1260,7 +1545,12 @@ async fn test_ui_snapshot_help_mode_scrolled_bottom() -> Result<()> {
             task_ids: &task_ids,
             now: jiff_now,
             show_details: false,
+            details_scroll_offset: 0,
             filter_text: "",
+            group_filter: None,
+            status_filter: None,
+            daemon: None,
+            daemons: &[],
             input_mode: false,
             sort_mode: false,
             sort_field: SortField::default(),
@@ -1270,6 +1560,16 @@ async fn test_ui_snapshot_help_mode_scrolled_bottom() -> Result<()> {
             selected_task_ids: &HashSet::new(),
             help_mode: true,
             help_scroll_offset: max_offset,
+            group_picker: None,
+            clean_options: None,
+            confirm_reset: false,
+            status_message: None,
+            deps_view: None,
+            env_view: None,
+            history: None,
+            history_view: None,
+            events_view: None,
+            preview: None,
             custom_commands: &custom_commands,
             config_path: Some(std::path::Path::new("/home/user/.config/pui/config.toml")),
         };
@@ -1417,3 +1717,2916 @@ simple = { key = "t", cmd = ["echo", "test"] }
     let simple = config.custom_commands.get("simple").unwrap();
     assert_eq!(simple.key, "t");
 }
+
+/// Build a state with queued tasks 0..count, and "default" and "gpu" groups
+fn queued_tasks_state(count: usize) -> State {
+    let mut state = State::default();
+    let now = Local.timestamp_opt(1767225600, 0).unwrap();
+
+    for id in 0..count {
+        let task = Task {
+            id,
+            created_at: now,
+            original_command: format!("task_{id}"),
+            command: format!("task_{id}"),
+            path: PathBuf::from("/tmp"),
+            envs: HashMap::new(),
+            group: "default".to_string(),
+            dependencies: vec![],
+            priority: 0,
+            label: None,
+            status: TaskStatus::Queued { enqueued_at: now },
+        };
+        state.tasks.insert(id, task);
+    }
+    for name in ["default", "gpu"] {
+        state.groups.insert(
+            name.to_string(),
+            Group {
+                status: GroupStatus::Running,
+                parallel_tasks: 1,
+            },
+        );
+    }
+    state
+}
+
+/// Test raising and lowering priority of the current task and of a multi-selection
+#[tokio::test]
+async fn test_change_priority() -> Result<()> {
+    use crate::App;
+
+    let state = queued_tasks_state(3);
+    let mock_client = MockPueueClient {
+        state: state.clone(),
+        log_delay: std::time::Duration::ZERO,
+        offline: Default::default(),
+    };
+    let mut app = App::new(mock_client, Config::default());
+    app.state = Some(state);
+    app.table_state.select(Some(0));
+    app.update_current_task_id();
+
+    let backend = TestBackend::new(80, 24);
+    let mut terminal = Terminal::new(backend)?;
+
+    // '+' raises the current task only
+    app.on_key_event(
+        KeyEvent::new(KeyCode::Char('+'), KeyModifiers::NONE),
+        &mut terminal,
+    )
+    .await?;
+    let priority = |app: &crate::App<MockPueueClient>, id: usize| {
+        app.state.as_ref().unwrap().tasks.get(&id).unwrap().priority
+    };
+    assert_eq!(priority(&app, 0), 1);
+    assert_eq!(priority(&app, 1), 0);
+
+    // '-' lowers all selected tasks
+    app.selected_task_ids.extend([1, 2]);
+    app.on_key_event(
+        KeyEvent::new(KeyCode::Char('-'), KeyModifiers::NONE),
+        &mut terminal,
+    )
+    .await?;
+    assert_eq!(priority(&app, 0), 1);
+    assert_eq!(priority(&app, 1), -1);
+    assert_eq!(priority(&app, 2), -1);
+
+    Ok(())
+}
+
+/// Test swapping two selected tasks, and that swapping requires exactly two tasks
+#[tokio::test]
+async fn test_switch_selected_tasks() -> Result<()> {
+    use crate::App;
+
+    let state = queued_tasks_state(3);
+    let mock_client = MockPueueClient {
+        state: state.clone(),
+        log_delay: std::time::Duration::ZERO,
+        offline: Default::default(),
+    };
+    let mut app = App::new(mock_client, Config::default());
+    app.state = Some(state);
+
+    app.selected_task_ids.insert(0);
+    app.switch_selected_tasks().await;
+    assert!(app.error_modal.is_some(), "Swapping needs two tasks");
+    app.error_modal = None;
+
+    app.selected_task_ids.insert(2);
+    app.switch_selected_tasks().await;
+    assert!(app.error_modal.is_none());
+    let tasks = &app.state.as_ref().unwrap().tasks;
+    assert_eq!(tasks.get(&0).unwrap().command, "task_2");
+    assert_eq!(tasks.get(&2).unwrap().command, "task_0");
+    assert!(app.selected_task_ids.is_empty());
+
+    Ok(())
+}
+
+/// Test moving a task to another group through the group picker
+#[tokio::test]
+async fn test_move_task_to_group() -> Result<()> {
+    use crate::App;
+
+    let mut state = queued_tasks_state(2);
+    state.tasks.get_mut(&1).unwrap().priority = 5;
+    let mock_client = MockPueueClient {
+        state: state.clone(),
+        log_delay: std::time::Duration::ZERO,
+        offline: Default::default(),
+    };
+    let mut app = App::new(mock_client, Config::default());
+    app.state = Some(state);
+    app.table_state.select(Some(1));
+    app.update_current_task_id();
+
+    let backend = TestBackend::new(80, 24);
+    let mut terminal = Terminal::new(backend)?;
+
+    // 'm' opens the picker on the task's current group; move down to "gpu" and confirm
+    for code in [KeyCode::Char('m'), KeyCode::Down, KeyCode::Enter] {
+        app.on_key_event(KeyEvent::new(code, KeyModifiers::NONE), &mut terminal)
+            .await?;
+    }
+
+    assert!(app.error_modal.is_none());
+    let tasks = &app.state.as_ref().unwrap().tasks;
+    assert!(!tasks.contains_key(&1), "Original task should be removed");
+    let moved = tasks.get(&2).expect("Task should be re-added");
+    assert_eq!(moved.group, "gpu");
+    assert_eq!(moved.command, "task_1");
+    assert_eq!(moved.priority, 5);
+    assert_eq!(tasks.get(&0).unwrap().group, "default");
+
+    Ok(())
+}
+
+/// Test that tasks other tasks wait for aren't moved, as their dependants would lose them
+#[tokio::test]
+async fn test_move_task_with_dependants() -> Result<()> {
+    use crate::App;
+
+    let mut state = queued_tasks_state(3);
+    state.tasks.get_mut(&2).unwrap().dependencies = vec![0];
+    let mock_client = MockPueueClient {
+        state: state.clone(),
+        log_delay: std::time::Duration::ZERO,
+        offline: Default::default(),
+    };
+    let mut app = App::new(mock_client, Config::default());
+    app.state = Some(state);
+    app.selected_task_ids = HashSet::from([0, 1]);
+
+    app.move_tasks_to_group("gpu").await;
+
+    assert_eq!(
+        app.error_modal.as_deref(),
+        Some("Task(s) 0 not moved: other tasks depend on them")
+    );
+    let tasks = &app.state.as_ref().unwrap().tasks;
+    assert_eq!(tasks[&0].group, "default");
+    assert_eq!(tasks[&2].dependencies, vec![0]);
+    // The other task is moved as usual
+    assert!(!tasks.contains_key(&1));
+    assert_eq!(tasks[&3].command, "task_1");
+    assert_eq!(tasks[&3].group, "gpu");
+
+    Ok(())
+}
+
+/// Test that a move whose original task can't be removed takes the copy out again
+#[tokio::test]
+async fn test_move_task_rolls_back() -> Result<()> {
+    use crate::App;
+
+    let state = queued_tasks_state(2);
+    let mut mock_client = MockPueueClient {
+        state: state.clone(),
+        log_delay: std::time::Duration::ZERO,
+        offline: Default::default(),
+    };
+    // The task started since the last refresh, so the daemon won't remove it
+    let now = Local.timestamp_opt(1767225600, 0).unwrap();
+    mock_client.state.tasks.get_mut(&1).unwrap().status = TaskStatus::Running {
+        enqueued_at: now,
+        start: now,
+    };
+    let mut app = App::new(mock_client, Config::default());
+    app.state = Some(state);
+    app.selected_task_ids = HashSet::from([1]);
+
+    app.move_tasks_to_group("gpu").await;
+
+    assert_eq!(
+        app.error_modal.as_deref(),
+        Some("Failed to move task(s), they were left as they were: Task 1 is running")
+    );
+    let ids: Vec<usize> = app.state.as_ref().unwrap().tasks.keys().copied().collect();
+    assert_eq!(ids, vec![0, 1]);
+
+    Ok(())
+}
+
+/// Test cleaning finished tasks reports the number removed, and respects the successful-only option
+#[tokio::test]
+async fn test_clean_finished_tasks() -> Result<()> {
+    use crate::App;
+
+    // Task 0 running, task 1 succeeded, task 2 failed
+    let mut mock_client = MockPueueClient::new();
+    let state = mock_client.get_state().await?;
+    let mut app = App::new(mock_client, Config::default());
+    app.state = Some(state);
+
+    let backend = TestBackend::new(80, 24);
+    let mut terminal = Terminal::new(backend)?;
+
+    // Successful only: 'C', toggle 's', confirm
+    for code in [KeyCode::Char('C'), KeyCode::Char('s'), KeyCode::Enter] {
+        app.on_key_event(KeyEvent::new(code, KeyModifiers::NONE), &mut terminal)
+            .await?;
+    }
+    assert_eq!(
+        app.status_message.as_deref(),
+        Some("Cleaned 1 finished task(s)")
+    );
+    let ids: Vec<usize> = app.state.as_ref().unwrap().tasks.keys().copied().collect();
+    assert_eq!(ids, vec![0, 2]);
+
+    // All finished tasks
+    app.clean_tasks(CleanOptions::default()).await;
+    assert_eq!(
+        app.status_message.as_deref(),
+        Some("Cleaned 1 finished task(s)")
+    );
+    let ids: Vec<usize> = app.state.as_ref().unwrap().tasks.keys().copied().collect();
+    assert_eq!(ids, vec![0]);
+
+    Ok(())
+}
+
+/// Test that the number of cleaned tasks doesn't depend on how old the last refresh is
+#[tokio::test]
+async fn test_clean_counts_tasks_finished_since_refresh() -> Result<()> {
+    use crate::App;
+
+    let mut mock_client = MockPueueClient::new();
+    let mut state = mock_client.get_state().await?;
+    // Task 0 finished after the last refresh, and task 1 was removed by someone else
+    let now = Local.timestamp_opt(1767225600, 0).unwrap();
+    mock_client.state.tasks.get_mut(&0).unwrap().status = TaskStatus::Done {
+        enqueued_at: now,
+        start: now,
+        end: now,
+        result: TaskResult::Success,
+    };
+    mock_client.state.tasks.remove(&1);
+    state.tasks.get_mut(&2).unwrap().status = TaskStatus::Running {
+        enqueued_at: now,
+        start: now,
+    };
+    let mut app = App::new(mock_client, Config::default());
+    app.state = Some(state);
+
+    app.clean_tasks(CleanOptions::default()).await;
+    assert_eq!(
+        app.status_message.as_deref(),
+        Some("Cleaned 2 finished task(s)")
+    );
+    assert!(app.state.as_ref().unwrap().tasks.is_empty());
+
+    Ok(())
+}
+
+/// Test that resetting the daemon requires confirmation
+#[tokio::test]
+async fn test_reset_daemon_requires_confirmation() -> Result<()> {
+    use crate::App;
+
+    let mut mock_client = MockPueueClient::new();
+    let state = mock_client.get_state().await?;
+    let mut app = App::new(mock_client, Config::default());
+    app.state = Some(state);
+
+    let backend = TestBackend::new(80, 24);
+    let mut terminal = Terminal::new(backend)?;
+
+    // Any key other than 'y' cancels
+    for code in [KeyCode::Char('C'), KeyCode::Char('R'), KeyCode::Char('n')] {
+        app.on_key_event(KeyEvent::new(code, KeyModifiers::NONE), &mut terminal)
+            .await?;
+    }
+    assert_eq!(app.state.as_ref().unwrap().tasks.len(), 3);
+
+    for code in [KeyCode::Char('C'), KeyCode::Char('R')] {
+        app.on_key_event(KeyEvent::new(code, KeyModifiers::NONE), &mut terminal)
+            .await?;
+    }
+    terminal.draw(|f| app.draw(f))?;
+    let ui = buffer_contents(terminal.backend().buffer());
+    assert!(ui.contains("Reset daemon"));
+
+    app.on_key_event(
+        KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE),
+        &mut terminal,
+    )
+    .await?;
+    assert!(app.state.as_ref().unwrap().tasks.is_empty());
+    assert_eq!(
+        app.status_message.as_deref(),
+        Some("Reset daemon, removed 3 task(s)")
+    );
+
+    Ok(())
+}
+
+/// Build a state where 2 depends on 0 and 1, and 3 depends on 2
+fn dependency_state() -> State {
+    let mut state = queued_tasks_state(4);
+    let now = Local.timestamp_opt(1767225600, 0).unwrap();
+    state.tasks.get_mut(&0).unwrap().status = TaskStatus::Done {
+        enqueued_at: now,
+        start: now,
+        end: now,
+        result: TaskResult::Success,
+    };
+    state.tasks.get_mut(&1).unwrap().status = TaskStatus::Done {
+        enqueued_at: now,
+        start: now,
+        end: now,
+        result: TaskResult::Failed(1),
+    };
+    state.tasks.get_mut(&2).unwrap().dependencies = vec![0, 1];
+    state.tasks.get_mut(&3).unwrap().dependencies = vec![2];
+    state
+}
+
+/// Test the dependency graph view of a task with both parents and children
+#[tokio::test]
+async fn test_ui_snapshot_dependency_view() -> Result<()> {
+    use crate::App;
+
+    unsafe {
+        std::env::set_var("TZ", "UTC");
+    }
+
+    let state = dependency_state();
+    let mock_client = MockPueueClient {
+        state: state.clone(),
+        log_delay: std::time::Duration::ZERO,
+        offline: Default::default(),
+    };
+    let mut app = App::new(mock_client, Config::default());
+    app.state = Some(state);
+    app.table_state.select(Some(2));
+    app.update_current_task_id();
+
+    let backend = TestBackend::new(80, 24);
+    let mut terminal = Terminal::new(backend)?;
+
+    app.on_key_event(
+        KeyEvent::new(KeyCode::Char('D'), KeyModifiers::NONE),
+        &mut terminal,
+    )
+    .await?;
+    terminal.draw(|f| app.draw(f))?;
+
+    let ui = buffer_contents(terminal.backend().buffer());
+    insta::assert_snapshot!(ui);
+
+    Ok(())
+}
+
+/// Test navigating the dependency graph and jumping to a task in the table
+#[tokio::test]
+async fn test_dependency_view_navigation() -> Result<()> {
+    use crate::deps::{DepRelation, dependency_lines};
+    use crate::{App, AppMode};
+
+    let state = dependency_state();
+    let lines = dependency_lines(&state, &[2]);
+    let line_ids: Vec<(usize, DepRelation)> =
+        lines.iter().map(|l| (l.task_id, l.relation)).collect();
+    assert_eq!(
+        line_ids,
+        vec![
+            (2, DepRelation::Root),
+            (0, DepRelation::DependsOn),
+            (1, DepRelation::DependsOn),
+            (3, DepRelation::RequiredBy),
+        ]
+    );
+
+    let mock_client = MockPueueClient {
+        state: state.clone(),
+        log_delay: std::time::Duration::ZERO,
+        offline: Default::default(),
+    };
+    let mut app = App::new(mock_client, Config::default());
+    app.state = Some(state);
+    app.table_state.select(Some(3));
+    app.update_current_task_id();
+    // Hide task 1 so jumping to it has to clear the filter
+    app.filter_text = "task_3".to_string();
+
+    let backend = TestBackend::new(80, 24);
+    let mut terminal = Terminal::new(backend)?;
+
+    // Open on task 3, re-root on its parent (task 2), go down to task 1 and jump to it
+    for code in [
+        KeyCode::Char('D'),
+        KeyCode::Down,
+        KeyCode::Char('l'),
+        KeyCode::Down,
+        KeyCode::Down,
+        KeyCode::Enter,
+    ] {
+        app.on_key_event(KeyEvent::new(code, KeyModifiers::NONE), &mut terminal)
+            .await?;
+    }
+
+    assert_eq!(app.current_task_id, Some(1));
+    assert!(app.filter_text.is_empty());
+    assert_eq!(app.table_state.selected(), Some(1));
+
+    // The graph is kept until the state changes
+    app.on_key_event(
+        KeyEvent::new(KeyCode::Char('D'), KeyModifiers::NONE),
+        &mut terminal,
+    )
+    .await?;
+    let AppMode::Deps(deps_state) = &app.app_mode else {
+        panic!("Expected the dependency view");
+    };
+    assert_eq!(deps_state.lines.len(), 3);
+    app.pueue_client
+        .state
+        .tasks
+        .get_mut(&2)
+        .unwrap()
+        .dependencies = vec![0];
+    app.refresh_state().await?;
+    let AppMode::Deps(deps_state) = &app.app_mode else {
+        panic!("Expected the dependency view");
+    };
+    assert_eq!(deps_state.lines.len(), 1);
+
+    Ok(())
+}
+
+/// Test that tasks shared by several branches of the graph are only expanded once
+#[test]
+fn test_dependency_lines_shared_tasks() {
+    use crate::deps::{DepRelation, dependency_lines};
+
+    // 60 layers of two tasks, each depending on both tasks of the layer before
+    let mut state = queued_tasks_state(120);
+    for id in 2..120 {
+        let layer = id / 2;
+        state.tasks.get_mut(&id).unwrap().dependencies = vec![layer * 2 - 2, layer * 2 - 1];
+    }
+
+    // Every ancestor is listed once in full, and once more from the other task of its layer
+    let lines = dependency_lines(&state, &[119]);
+    assert_eq!(lines.iter().filter(|l| !l.repeated).count(), 119);
+    assert_eq!(lines.len(), 235);
+    let lines = dependency_lines(&state, &[0]);
+    assert_eq!(lines.iter().filter(|l| !l.repeated).count(), 119);
+    assert_eq!(lines.len(), 235);
+
+    let state = {
+        let mut state = queued_tasks_state(4);
+        state.tasks.get_mut(&1).unwrap().dependencies = vec![0];
+        state.tasks.get_mut(&2).unwrap().dependencies = vec![0];
+        state.tasks.get_mut(&3).unwrap().dependencies = vec![1, 2];
+        state
+    };
+    let lines: Vec<(usize, usize, DepRelation, bool)> = dependency_lines(&state, &[3])
+        .iter()
+        .map(|l| (l.task_id, l.depth, l.relation, l.repeated))
+        .collect();
+    assert_eq!(
+        lines,
+        vec![
+            (3, 0, DepRelation::Root, false),
+            (1, 1, DepRelation::DependsOn, false),
+            (0, 2, DepRelation::DependsOn, false),
+            (2, 1, DepRelation::DependsOn, false),
+            (0, 2, DepRelation::DependsOn, true),
+        ]
+    );
+}
+
+fn env_state_with_vars() -> State {
+    let mut state = MockPueueClient::new().state;
+    let envs = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    };
+    state.tasks.get_mut(&1).unwrap().envs = envs(&[
+        ("HOME", "/home/user"),
+        ("PATH", "/usr/bin:/bin"),
+        ("RUST_LOG", "info"),
+    ]);
+    state.tasks.get_mut(&2).unwrap().envs =
+        envs(&[("HOME", "/home/user"), ("PATH", "/opt/bin:/usr/bin:/bin")]);
+    state
+}
+
+/// Test the environment viewer filters variables and diffs two selected tasks
+#[tokio::test]
+async fn test_ui_snapshot_env_view_diff() -> Result<()> {
+    use crate::App;
+
+    unsafe {
+        std::env::set_var("TZ", "UTC");
+    }
+
+    let state = env_state_with_vars();
+    let mock_client = MockPueueClient {
+        state: state.clone(),
+        log_delay: std::time::Duration::ZERO,
+        offline: Default::default(),
+    };
+    let mut app = App::new(mock_client, Config::default());
+    app.state = Some(state);
+    app.selected_task_ids.extend([1, 2]);
+
+    let backend = TestBackend::new(80, 24);
+    let mut terminal = Terminal::new(backend)?;
+
+    app.on_key_event(
+        KeyEvent::new(KeyCode::Char('e'), KeyModifiers::NONE),
+        &mut terminal,
+    )
+    .await?;
+    terminal.draw(|f| app.draw(f))?;
+
+    let ui = buffer_contents(terminal.backend().buffer());
+    assert!(ui.contains("PATH"));
+    assert!(ui.contains("RUST_LOG"));
+    assert!(!ui.contains("HOME"), "Identical variables should be hidden");
+    insta::assert_snapshot!(ui);
+
+    Ok(())
+}
+
+/// Test editing a finished task's environment queues a copy with the new environment
+#[tokio::test]
+async fn test_env_view_edit_and_restart() -> Result<()> {
+    use crate::App;
+
+    let state = env_state_with_vars();
+    let mock_client = MockPueueClient {
+        state: state.clone(),
+        log_delay: std::time::Duration::ZERO,
+        offline: Default::default(),
+    };
+    let mut app = App::new(mock_client, Config::default());
+    app.state = Some(state);
+    app.table_state.select(Some(1));
+    app.update_current_task_id();
+
+    let backend = TestBackend::new(80, 24);
+    let mut terminal = Terminal::new(backend)?;
+
+    let mut keys = vec![KeyCode::Char('e')];
+    // Filter to RUST_LOG and change its value to "debug"
+    keys.push(KeyCode::Char('/'));
+    keys.extend("rust".chars().map(KeyCode::Char));
+    keys.push(KeyCode::Enter);
+    keys.push(KeyCode::Char('e'));
+    keys.extend(std::iter::repeat_n(KeyCode::Backspace, 4));
+    keys.extend("debug".chars().map(KeyCode::Char));
+    keys.push(KeyCode::Enter);
+    // Add a new variable
+    keys.push(KeyCode::Char('a'));
+    keys.extend("NEW=1".chars().map(KeyCode::Char));
+    keys.push(KeyCode::Enter);
+    keys.push(KeyCode::Char('r'));
+    for code in keys {
+        app.on_key_event(KeyEvent::new(code, KeyModifiers::NONE), &mut terminal)
+            .await?;
+    }
+
+    assert!(app.error_modal.is_none(), "{:?}", app.error_modal);
+    assert_eq!(
+        app.status_message.as_deref(),
+        Some("Queued task 1 as task 3 with modified environment")
+    );
+    let tasks = &app.state.as_ref().unwrap().tasks;
+    let new_task = tasks.get(&3).unwrap();
+    assert_eq!(new_task.command, "echo 'hello'");
+    assert_eq!(new_task.envs.get("RUST_LOG").unwrap(), "debug");
+    assert_eq!(new_task.envs.get("NEW").unwrap(), "1");
+    assert_eq!(new_task.envs.get("PATH").unwrap(), "/usr/bin:/bin");
+    // The original task is untouched
+    assert_eq!(tasks.get(&1).unwrap().envs.get("RUST_LOG").unwrap(), "info");
+
+    Ok(())
+}
+
+/// Test the details popup scrolled to the bottom, for a failed task with a dependency
+#[tokio::test]
+async fn test_ui_snapshot_details_scrolled_bottom() -> Result<()> {
+    let (mut state, task_ids, mut terminal, jiff_now) = setup_test_ui().await?;
+    let mut table_state = TableState::default();
+    table_state.select(Some(2));
+
+    if let Some(task) = state.tasks.get_mut(&2) {
+        task.dependencies = vec![1];
+        task.label = Some("nightly".to_string());
+        task.original_command = "fail".to_string();
+    }
+    let details_text = ui::build_details_text(
+        2,
+        state.tasks.get(&2).unwrap(),
+        &state,
+        &[],
+        &jiff_now,
+        None,
+    );
+    assert!(details_text.contains("Result: Exited with non-zero code 1"));
+    assert!(details_text.contains("#1 Success  echo 'hello'"));
+    assert!(details_text.contains("Original Command: fail\nEffective Command: false"));
+
+    let modal_area = ui::centered_rect(60, 60, Rect::new(0, 0, 80, 24));
+    let content_height = modal_area.height.saturating_sub(2);
+    let content_width = modal_area.width.saturating_sub(2).max(1);
+    let line_count = ui::modal_line_count(&details_text, content_width, content_height);
+    let max_offset = line_count.saturating_sub(content_height);
+
+    terminal.draw(|f| {
+        let mut ui_state = ui::UiState {
+            state: &Some(state),
+            table_state: &mut table_state,
+            task_ids: &task_ids,
+            now: jiff_now,
+            show_details: true,
+            details_scroll_offset: max_offset,
+            filter_text: "",
+            group_filter: None,
+            status_filter: None,
+            daemon: None,
+            daemons: &[],
+            input_mode: false,
+            sort_mode: false,
+            sort_field: SortField::default(),
+            log_view: None,
+            connection_error: None,
+            error_modal: None,
+            selected_task_ids: &HashSet::new(),
+            help_mode: false,
+            help_scroll_offset: 0,
+            group_picker: None,
+            clean_options: None,
+            confirm_reset: false,
+            status_message: None,
+            deps_view: None,
+            env_view: None,
+            history: None,
+            history_view: None,
+            events_view: None,
+            preview: None,
+            custom_commands: &BTreeMap::new(),
+            config_path: None,
+        };
+        ui::draw(f, &mut ui_state);
+    })?;
+
+    let ui = buffer_contents(terminal.backend().buffer());
+
+    insta::assert_snapshot!(ui);
+
+    Ok(())
+}
+
+/// Test that a finished task's log is fetched in one go, without opening a stream
+#[tokio::test]
+async fn test_finished_task_log_is_static() -> Result<()> {
+    use crate::{App, AppMode};
+
+    let (state, _, mut terminal, _) = setup_test_ui().await?;
+    let mock_client = MockPueueClient {
+        state: state.clone(),
+        log_delay: std::time::Duration::ZERO,
+        offline: Default::default(),
+    };
+    let mut app = App::new(mock_client, Config::default());
+    app.state = Some(state);
+    // Task 1 has succeeded
+    app.table_state.select(Some(1));
+
+    let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+    app.on_key_event(enter, &mut terminal).await?;
+
+    let AppMode::Log(log_state) = &app.app_mode else {
+        panic!("Expected log mode, got {:?}", app.app_mode);
+    };
+    assert!(app.stream_clients.is_empty());
+    assert!(!log_state.streaming);
+    assert_eq!(
+        log_state.lines().collect::<Vec<_>>(),
+        ["Full log of task 1", "Line 2", "Line 3"]
+    );
+    assert_eq!(log_state.summary(), "Task Log #1 | Success | 3 lines, 32 B");
+
+    Ok(())
+}
+
+/// Test that `log_lines` limits the fetched output and marks it as truncated
+#[tokio::test]
+async fn test_finished_task_log_last_lines() -> Result<()> {
+    use crate::{App, AppMode};
+
+    let (state, _, mut terminal, _) = setup_test_ui().await?;
+    let mock_client = MockPueueClient {
+        state: state.clone(),
+        log_delay: std::time::Duration::ZERO,
+        offline: Default::default(),
+    };
+    let config = Config {
+        log_lines: Some(2),
+        ..Config::default()
+    };
+    let mut app = App::new(mock_client, config);
+    app.state = Some(state);
+    app.table_state.select(Some(2));
+
+    let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+    app.on_key_event(enter, &mut terminal).await?;
+
+    let AppMode::Log(log_state) = &app.app_mode else {
+        panic!("Expected log mode, got {:?}", app.app_mode);
+    };
+    assert_eq!(log_state.lines().collect::<Vec<_>>(), ["Line 2", "Line 3"]);
+    assert!(!log_state.output_complete);
+    assert!(log_state.summary().contains("| Failed (1) |"));
+    assert!(log_state.summary().contains("| truncated"));
+
+    Ok(())
+}
+
+#[test]
+fn test_decompress_log() {
+    use std::io::Write;
+
+    let mut encoder = snap::write::FrameEncoder::new(Vec::new());
+    encoder.write_all(b"hello\nworld\n").unwrap();
+    let compressed = encoder.into_inner().unwrap();
+    assert_eq!(decompress_log(&compressed), "hello\nworld\n");
+
+    // The daemon sends uncompressed text when it fails to read a log file
+    assert_eq!(
+        decompress_log(b"Failed reading process output file"),
+        "Failed reading process output file"
+    );
+}
+
+/// Test that chunks are split into lines as they arrive, continuing unfinished lines
+#[test]
+fn test_log_state_chunks_continue_lines() {
+    let mut log_state = LogState::new(vec![0]);
+    log_state.push_str(0, "first li");
+    log_state.push_str(0, "ne\nsecond\n");
+    log_state.push_str(0, "third");
+
+    assert_eq!(log_state.line_count(), 3);
+    assert_eq!(
+        log_state.lines().collect::<Vec<_>>(),
+        ["first line", "second", "third"]
+    );
+    assert!(log_state.summary().contains("| 3 lines, 23 B"));
+}
+
+/// Test that the oldest lines are dropped once the cap is reached
+#[test]
+fn test_log_state_drops_oldest_lines() {
+    let mut log_state = LogState::new(vec![0]);
+    log_state.max_lines = 3;
+    for i in 0..5 {
+        log_state.push_str(0, &format!("line {}\n", i));
+    }
+
+    assert_eq!(log_state.line_count(), 5);
+    assert_eq!(
+        log_state.lines().collect::<Vec<_>>(),
+        ["line 2", "line 3", "line 4"]
+    );
+    assert!(log_state.summary().contains("| first 2 dropped"));
+}
+
+/// Test scrolling through wrapped lines a row at a time
+#[test]
+fn test_log_state_scrolls_wrapped_rows() {
+    let mut log_state = LogState::new(vec![0]);
+    // At width 10, "b" line wraps into 3 rows
+    log_state.push_str(0, "a\nbbbbbbbbbbbbbbbbbbbbbbbbb\nc\nd");
+
+    let down = KeyEvent::new(KeyCode::Down, KeyModifiers::NONE);
+    let up = KeyEvent::new(KeyCode::Up, KeyModifiers::NONE);
+    let (page_height, page_width) = (2, 10);
+
+    log_state.handle_key(down, page_height, page_width);
+    log_state.handle_key(down, page_height, page_width);
+    assert_eq!(log_state.visible_lines(page_height).1, 1);
+    assert_eq!(
+        log_state.visible_lines(page_height).0[0],
+        Line::from("bbbbbbbbbbbbbbbbbbbbbbbbb")
+    );
+
+    // 6 rows in total, so the last page starts on the 5th row ("c")
+    for _ in 0..10 {
+        log_state.handle_key(down, page_height, page_width);
+    }
+    assert_eq!(
+        log_state.visible_lines(page_height),
+        (vec![Line::from("c"), Line::from("d")], 0)
+    );
+
+    log_state.handle_key(up, page_height, page_width);
+    assert_eq!(log_state.visible_lines(page_height).1, 2);
+}
+
+#[test]
+fn test_strip_ansi_codes() {
+    assert_eq!(
+        log::strip_ansi_codes("\x1b[1;31merror\x1b[0m: failed"),
+        "error: failed"
+    );
+    assert_eq!(
+        log::strip_ansi_codes("\x1b]0;title\x07plain \x1b]8;;url\x1b\\link"),
+        "plain link"
+    );
+    assert_eq!(log::strip_ansi_codes("no escapes"), "no escapes");
+}
+
+/// Test saving a log with a header and without colours
+#[tokio::test]
+async fn test_save_log_with_header() -> Result<()> {
+    let (state, _, _, _) = setup_test_ui().await?;
+    let mut log_state = LogState::new(vec![1]);
+    log_state.push_str(1, "\x1b[32mok\x1b[0m\ndone\n");
+
+    let temp_dir = tempfile::tempdir()?;
+    let path = temp_dir.path().join("pueue-1.log");
+    let header = log::log_header("1", state.tasks.get(&1).unwrap());
+    let lines = log_state.save(&path, Some(&header), true)?;
+
+    assert_eq!(lines, 2);
+    let content = std::fs::read_to_string(&path)?;
+    assert!(content.starts_with("# Task: 1\n# Command: echo 'hello'\n# Path: "));
+    assert!(content.contains("# Status: Success\n"));
+    assert!(content.ends_with("#\nok\ndone\n"));
+
+    // Errors are returned rather than panicking
+    assert!(
+        log_state
+            .save(&temp_dir.path().join("missing/dir.log"), None, false)
+            .is_err()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ui_snapshot_log_save_prompt() -> Result<()> {
+    let backend = TestBackend::new(100, 5);
+    let mut terminal = Terminal::new(backend)?;
+    let mut table_state = TableState::default();
+
+    let mut log_state = LogState::new(vec![3]);
+    log_state.push_str(3, "building...\n");
+    log_state.save_prompt = Some(log::SavePrompt {
+        path: "/tmp/pueue-3.log".to_string(),
+        strip_ansi: true,
+        header: false,
+    });
+
+    terminal.draw(|f| {
+        let mut ui_state = ui::UiState {
+            state: &None,
+            table_state: &mut table_state,
+            task_ids: &[],
+            now: jiff::Timestamp::now(),
+            show_details: false,
+            details_scroll_offset: 0,
+            filter_text: "",
+            group_filter: None,
+            status_filter: None,
+            daemon: None,
+            daemons: &[],
+            input_mode: false,
+            sort_mode: false,
+            sort_field: SortField::default(),
+            log_view: Some(&log_state),
+            connection_error: None,
+            error_modal: None,
+            selected_task_ids: &HashSet::new(),
+            help_mode: false,
+            help_scroll_offset: 0,
+            group_picker: None,
+            clean_options: None,
+            confirm_reset: false,
+            status_message: None,
+            deps_view: None,
+            env_view: None,
+            history: None,
+            history_view: None,
+            events_view: None,
+            preview: None,
+            custom_commands: &BTreeMap::new(),
+            config_path: None,
+        };
+        ui::draw(f, &mut ui_state);
+    })?;
+
+    let ui = buffer_contents(terminal.backend().buffer());
+
+    insta::assert_snapshot!(ui);
+
+    Ok(())
+}
+
+/// Test that turning wrapping off gives one row per line and clamps horizontal scrolling
+#[test]
+fn test_log_state_no_wrap_scrolls_horizontally() {
+    let mut log_state = LogState::new(vec![1]);
+    log_state.push_str(1, &format!("{}\nshort\n", "x".repeat(30)));
+    log_state.handle_key(KeyEvent::new(KeyCode::Home, KeyModifiers::NONE), 2, 10);
+
+    // Horizontal scrolling only applies when not wrapping
+    let right = KeyEvent::new(KeyCode::Char('l'), KeyModifiers::NONE);
+    assert!(!log_state.handle_key(right, 2, 10));
+
+    log_state.handle_key(KeyEvent::new(KeyCode::Char('w'), KeyModifiers::NONE), 2, 10);
+    assert!(!log_state.wrap);
+    assert!(log_state.summary().contains("no wrap"));
+
+    log_state.handle_key(right, 2, 10);
+    assert_eq!(log_state.left, 8);
+
+    // Scrolling right stops once the end of the widest line is in view
+    for _ in 0..5 {
+        log_state.handle_key(right, 2, 10);
+    }
+    assert_eq!(log_state.left, 20);
+    log_state.handle_key(KeyEvent::new(KeyCode::Char('0'), KeyModifiers::NONE), 2, 10);
+    assert_eq!(log_state.left, 0);
+    log_state.handle_key(KeyEvent::new(KeyCode::Char('$'), KeyModifiers::NONE), 2, 10);
+    assert_eq!(log_state.left, 20);
+
+    // Turning wrapping back on resets the horizontal scroll
+    log_state.handle_key(KeyEvent::new(KeyCode::Char('w'), KeyModifiers::NONE), 2, 10);
+    assert_eq!(log_state.left, 0);
+}
+
+/// Test that `:N` jumps to a line, clamped to the last page
+#[test]
+fn test_log_state_jump_to_line() {
+    let mut log_state = LogState::new(vec![1]);
+    for i in 1..=20 {
+        log_state.push_str(1, &format!("line {}\n", i));
+    }
+    assert!(log_state.autoscroll);
+
+    for c in [':', '1', '2'] {
+        log_state.handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE), 5, 40);
+    }
+    assert_eq!(log_state.jump_input.as_deref(), Some("12"));
+    log_state.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE), 5, 40);
+    assert_eq!(log_state.jump_input, None);
+    assert!(!log_state.autoscroll);
+    assert_eq!(log_state.visible_lines(5).0[0].to_string(), "line 12");
+
+    // Past the end shows the last page
+    for c in [':', '9', '9'] {
+        log_state.handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE), 5, 40);
+    }
+    log_state.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE), 5, 40);
+    assert_eq!(log_state.visible_lines(5).0[0].to_string(), "line 16");
+
+    // Esc cancels without moving
+    log_state.handle_key(KeyEvent::new(KeyCode::Char(':'), KeyModifiers::NONE), 5, 40);
+    log_state.handle_key(KeyEvent::new(KeyCode::Char('1'), KeyModifiers::NONE), 5, 40);
+    log_state.handle_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE), 5, 40);
+    assert_eq!(log_state.jump_input, None);
+    assert_eq!(log_state.visible_lines(5).0[0].to_string(), "line 16");
+}
+
+/// Test the line number gutter lines up with wrapped rows
+#[tokio::test]
+async fn test_ui_snapshot_log_line_numbers() -> Result<()> {
+    let backend = TestBackend::new(30, 8);
+    let mut terminal = Terminal::new(backend)?;
+    let mut table_state = TableState::default();
+
+    let mut log_state = LogState::new(vec![3]);
+    for i in 1..=9 {
+        log_state.push_str(3, &format!("line {}\n", i));
+    }
+    log_state.push_str(3, "a long line that wraps around\nlast\n");
+    log_state.line_numbers = true;
+    log_state.update_autoscroll(6, 28);
+
+    terminal.draw(|f| {
+        let mut ui_state = ui::UiState {
+            state: &None,
+            table_state: &mut table_state,
+            task_ids: &[],
+            now: jiff::Timestamp::now(),
+            show_details: false,
+            details_scroll_offset: 0,
+            filter_text: "",
+            group_filter: None,
+            status_filter: None,
+            daemon: None,
+            daemons: &[],
+            input_mode: false,
+            sort_mode: false,
+            sort_field: SortField::default(),
+            log_view: Some(&log_state),
+            connection_error: None,
+            error_modal: None,
+            selected_task_ids: &HashSet::new(),
+            help_mode: false,
+            help_scroll_offset: 0,
+            group_picker: None,
+            clean_options: None,
+            confirm_reset: false,
+            status_message: None,
+            deps_view: None,
+            env_view: None,
+            history: None,
+            history_view: None,
+            events_view: None,
+            preview: None,
+            custom_commands: &BTreeMap::new(),
+            config_path: None,
+        };
+        ui::draw(f, &mut ui_state);
+    })?;
+
+    let ui = buffer_contents(terminal.backend().buffer());
+
+    insta::assert_snapshot!(ui);
+
+    Ok(())
+}
+
+/// Test that highlight rules style matches and n/N/E jump between highlighted lines
+#[test]
+fn test_log_state_highlights() -> Result<()> {
+    let mut log_state = LogState::new(vec![1]);
+    for i in 1..=20 {
+        match i {
+            5 => log_state.push_str(1, "warning: unused variable\n"),
+            // Highlights are found once the line is complete across chunks
+            12 => {
+                log_state.push_str(1, "err");
+                log_state.push_str(1, "or: mismatched types\n");
+            }
+            _ => log_state.push_str(1, &format!("line {}\n", i)),
+        }
+    }
+    log_state.set_highlights(log::compile_highlights(
+        &Config::default().highlight_rules(),
+    )?);
+    assert_eq!(log_state.first_error_line(), Some(12));
+
+    let key = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+    log_state.handle_key(key('g'), 3, 40);
+    log_state.handle_key(key('n'), 3, 40);
+    let (lines, _) = log_state.visible_lines(3);
+    assert_eq!(lines[0].to_string(), "warning: unused variable");
+    assert_eq!(lines[0].spans[0].content, "warning");
+    assert_eq!(lines[0].spans[0].style.fg, Some(Color::Yellow));
+
+    log_state.handle_key(key('n'), 3, 40);
+    assert_eq!(
+        log_state.visible_lines(3).0[0].to_string(),
+        "error: mismatched types"
+    );
+    log_state.handle_key(key('n'), 3, 40);
+    assert_eq!(
+        log_state.message.as_deref(),
+        Some("No more highlighted lines below")
+    );
+
+    log_state.handle_key(key('N'), 3, 40);
+    assert_eq!(
+        log_state.visible_lines(3).0[0].to_string(),
+        "warning: unused variable"
+    );
+
+    log_state.handle_key(key('G'), 3, 40);
+    log_state.handle_key(key('E'), 3, 40);
+    assert!(!log_state.autoscroll);
+    assert_eq!(
+        log_state.visible_lines(3).0[0].to_string(),
+        "error: mismatched types"
+    );
+    Ok(())
+}
+
+/// Test the plain text task table printed by `pui list`
+#[tokio::test]
+async fn test_task_list_text() -> Result<()> {
+    let (state, task_ids, _, jiff_now) = setup_test_ui().await?;
+
+    insta::assert_snapshot!(ui::task_list_text(&state, &task_ids, &jiff_now, &[]));
+
+    Ok(())
+}
+
+/// Test the JSON printed by `pui list --json`
+#[tokio::test]
+async fn test_task_list_json() -> Result<()> {
+    let (state, task_ids, _, jiff_now) = setup_test_ui().await?;
+
+    insta::assert_snapshot!(ui::task_list_json(&state, &task_ids, &jiff_now, &[])?);
+
+    Ok(())
+}
+
+/// Test that `pui list` filters and sorts like the table
+#[tokio::test]
+async fn test_list_filters_and_sorts() -> Result<()> {
+    use crate::App;
+
+    let mut client = MockPueueClient::new();
+    let task = client.state.tasks.get_mut(&1).unwrap();
+    task.command = "./scripts/build.sh --release".to_string();
+    task.path = PathBuf::from("/srv/app");
+    let mut app = App::new(client, Config::default());
+
+    let ids = |text: &str| -> Vec<String> {
+        text.lines()
+            .skip(1)
+            .map(|line| line.split_whitespace().next().unwrap().to_string())
+            .collect()
+    };
+    let text = app.list("", SortField::Status, false).await?;
+    assert!(text.starts_with("Id  Path suffix"));
+    assert_eq!(ids(&text), ["2", "0", "1"]);
+    assert_eq!(ids(&app.list("succ", SortField::Id, false).await?), ["1"]);
+
+    // The JSON has the table's columns as shown, and the command and path in full
+    let text = app.list("succ", SortField::Id, false).await?;
+    let row: Vec<&str> = text.lines().nth(1).unwrap().split_whitespace().collect();
+    assert_eq!(row[..4], ["1", "app/", "build.sh", "--release"]);
+    let json: serde_json::Value =
+        serde_json::from_str(&app.list("succ", SortField::Id, true).await?)?;
+    assert_eq!(json[0]["path"], "app/");
+    assert_eq!(json[0]["command"], "build.sh --release");
+    assert_eq!(json[0]["full_path"], "/srv/app");
+    assert_eq!(json[0]["full_command"], "./scripts/build.sh --release");
+    Ok(())
+}
+
+/// Test the command line flags, which apply to the TUI and `pui list`
+#[test]
+fn test_cli_flags() {
+    use crate::{Cli, Command};
+    use clap::Parser;
+
+    let cli = Cli::try_parse_from([
+        "pui", "--filter", "build", "--sort", "status", "--task", "3",
+    ])
+    .unwrap();
+    assert_eq!(cli.filter.as_deref(), Some("build"));
+    assert_eq!(cli.sort, Some(SortField::Status));
+    assert_eq!(cli.task, Some(3));
+    assert!(cli.command.is_none());
+
+    let cli = Cli::try_parse_from(["pui", "list", "--json", "--group", "gpu"]).unwrap();
+    assert!(matches!(cli.command, Some(Command::List { json: true })));
+    assert_eq!(cli.group.as_deref(), Some("gpu"));
+
+    let cli = Cli::try_parse_from([
+        "pui",
+        "--pueue-config",
+        "/srv/pueue.yml",
+        "--profile",
+        "work",
+    ])
+    .unwrap();
+    assert_eq!(cli.pueue_config, Some(PathBuf::from("/srv/pueue.yml")));
+    assert_eq!(cli.profile.as_deref(), Some("work"));
+
+    assert!(Cli::try_parse_from(["pui", "--sort", "size"]).is_err());
+}
+
+/// Test that the group filter hides tasks of other groups
+#[tokio::test]
+async fn test_group_filter() -> Result<()> {
+    use crate::App;
+
+    let mut client = MockPueueClient::new();
+    client.state.tasks.get_mut(&2).unwrap().group = "gpu".to_string();
+    let mut app = App::new(client, Config::default());
+    app.group_filter = Some("gpu".to_string());
+
+    let text = app.list("", SortField::Id, false).await?;
+    let ids: Vec<&str> = text
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().next())
+        .collect();
+    assert_eq!(ids, ["2"]);
+    Ok(())
+}
+
+/// Test that `--task` starts in the task's log viewer, within the `--group` if one is given
+#[tokio::test]
+async fn test_open_initial_task() -> Result<()> {
+    use crate::{App, AppMode};
+
+    let mut app = App::new(MockPueueClient::new(), Config::default());
+    app.group_filter = Some("default".to_string());
+    app.open_initial_task(1).await;
+    let AppMode::Log(log_state) = &app.app_mode else {
+        panic!("Expected log mode, got {:?}", app.app_mode);
+    };
+    assert_eq!(log_state.task_id, 1);
+    assert_eq!(app.current_task_id, Some(1));
+    assert_eq!(app.group_filter.as_deref(), Some("default"));
+
+    // A task outside the `--group` isn't opened, and the group is kept
+    let mut app = App::new(MockPueueClient::new(), Config::default());
+    app.group_filter = Some("gpu".to_string());
+    app.open_initial_task(1).await;
+    assert!(matches!(app.app_mode, AppMode::Normal));
+    assert_eq!(
+        app.error_modal.as_deref(),
+        Some("Task 1 is in group default, not gpu")
+    );
+    assert_eq!(app.group_filter.as_deref(), Some("gpu"));
+
+    let mut app = App::new(MockPueueClient::new(), Config::default());
+    app.open_initial_task(42).await;
+    assert!(matches!(app.app_mode, AppMode::Normal));
+    assert_eq!(app.error_modal.as_deref(), Some("Task 42 not found"));
+    Ok(())
+}
+
+/// Test that the title bar shows the daemon and the table title the group filter
+#[tokio::test]
+async fn test_ui_snapshot_daemon_and_group() -> Result<()> {
+    let (state, task_ids, mut terminal, jiff_now) = setup_test_ui().await?;
+    let mut table_state = TableState::default();
+    table_state.select(Some(0));
+
+    terminal.draw(|f| {
+        let mut ui_state = ui::UiState {
+            state: &Some(state),
+            table_state: &mut table_state,
+            task_ids: &task_ids,
+            now: jiff_now,
+            show_details: false,
+            details_scroll_offset: 0,
+            filter_text: "",
+            group_filter: Some("default"),
+            status_filter: None,
+            daemon: Some("work: tcp://build:6924"),
+            daemons: &[],
+            input_mode: false,
+            sort_mode: false,
+            sort_field: SortField::default(),
+            log_view: None,
+            connection_error: None,
+            error_modal: None,
+            selected_task_ids: &HashSet::new(),
+            help_mode: false,
+            help_scroll_offset: 0,
+            group_picker: None,
+            clean_options: None,
+            confirm_reset: false,
+            status_message: None,
+            deps_view: None,
+            env_view: None,
+            history: None,
+            history_view: None,
+            events_view: None,
+            preview: None,
+            custom_commands: &BTreeMap::new(),
+            config_path: None,
+        };
+        ui::draw(f, &mut ui_state);
+    })?;
+
+    let ui = buffer_contents(terminal.backend().buffer());
+
+    insta::assert_snapshot!(ui);
+
+    Ok(())
+}
+
+/// Test the daemon address shown for a pueue config
+#[test]
+fn test_daemon_address() {
+    use pueue_lib::settings::Shared;
+
+    let shared = Shared {
+        use_unix_socket: false,
+        host: "build".to_string(),
+        port: "6924".to_string(),
+        ..Shared::default()
+    };
+    assert_eq!(pueue_client::daemon_address(&shared), "tcp://build:6924");
+
+    let shared = Shared {
+        use_unix_socket: true,
+        unix_socket_path: Some(PathBuf::from("/shared/pueue.socket")),
+        ..Shared::default()
+    };
+    assert_eq!(
+        pueue_client::daemon_address(&shared),
+        "unix:/shared/pueue.socket"
+    );
+}
+
+/// Test that a missing pueue profile is reported rather than falling back to the default daemon
+#[tokio::test]
+async fn test_pueue_profile_not_found() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let config = dir.path().join("pueue.yml");
+    std::fs::write(&config, "profiles: {}\n")?;
+    let target = pueue_client::DaemonTarget {
+        config: Some(config),
+        profile: Some("work".to_string()),
+    };
+    let err = pueue_client::PueueClient::new(target).await.unwrap_err();
+    assert!(err.to_string().contains("work"), "{}", err);
+    Ok(())
+}
+
+/// Test the timestamp gutter marks output from before the log was opened
+#[tokio::test]
+async fn test_ui_snapshot_log_timestamps() -> Result<()> {
+    let backend = TestBackend::new(50, 6);
+    let mut terminal = Terminal::new(backend)?;
+    let mut table_state = TableState::default();
+
+    let mut log_state = LogState::new(vec![3]);
+    log_state.push_str(3, "building...\n");
+    let attached_at = log_state.attached_at;
+    log_state.push_streamed(
+        3,
+        "step 1\nstep ",
+        attached_at + jiff::SignedDuration::from_secs(2),
+    );
+    // A line continued in a later chunk keeps the time it started arriving
+    log_state.push_streamed(
+        3,
+        "2\ndone\n",
+        attached_at + jiff::SignedDuration::from_secs(83),
+    );
+    log_state.line_numbers = true;
+    log_state.handle_key(KeyEvent::new(KeyCode::Char('T'), KeyModifiers::NONE), 4, 48);
+    assert_eq!(log_state.timestamps, log::Timestamps::Relative);
+
+    terminal.draw(|f| {
+        let mut ui_state = ui::UiState {
+            state: &None,
+            table_state: &mut table_state,
+            task_ids: &[],
+            now: jiff::Timestamp::now(),
+            show_details: false,
+            details_scroll_offset: 0,
+            filter_text: "",
+            group_filter: None,
+            status_filter: None,
+            daemon: None,
+            daemons: &[],
+            input_mode: false,
+            sort_mode: false,
+            sort_field: SortField::default(),
+            log_view: Some(&log_state),
+            connection_error: None,
+            error_modal: None,
+            selected_task_ids: &HashSet::new(),
+            help_mode: false,
+            help_scroll_offset: 0,
+            group_picker: None,
+            clean_options: None,
+            confirm_reset: false,
+            status_message: None,
+            deps_view: None,
+            env_view: None,
+            history: None,
+            history_view: None,
+            events_view: None,
+            preview: None,
+            custom_commands: &BTreeMap::new(),
+            config_path: None,
+        };
+        ui::draw(f, &mut ui_state);
+    })?;
+
+    let ui = buffer_contents(terminal.backend().buffer());
+
+    insta::assert_snapshot!(ui);
+
+    Ok(())
+}
+
+/// Test the OSC 52 sequence, and its tmux passthrough wrapping
+#[test]
+fn test_osc52_sequence() {
+    assert_eq!(
+        clipboard::osc52_sequence("echo hi", false),
+        "\x1b]52;c;ZWNobyBoaQ==\x07"
+    );
+    assert_eq!(
+        clipboard::osc52_sequence("echo hi", true),
+        "\x1bPtmux;\x1b\x1b]52;c;ZWNobyBoaQ==\x07\x1b\\"
+    );
+}
+
+/// Test that `y` in the table copies the task's command with the configured clipboard command
+#[tokio::test]
+async fn test_yank_command_with_clipboard_command() -> Result<()> {
+    use crate::App;
+
+    let (state, _, mut terminal, _) = setup_test_ui().await?;
+    let dir = tempfile::tempdir()?;
+    let out = dir.path().join("clipboard");
+    let config = Config {
+        clipboard_command: Some(vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            format!("cat > {}", out.display()),
+        ]),
+        ..Config::default()
+    };
+    let mut app = App::new(
+        MockPueueClient {
+            state: state.clone(),
+            log_delay: std::time::Duration::ZERO,
+            offline: Default::default(),
+        },
+        config,
+    );
+    app.state = Some(state.clone());
+    app.current_task_id = Some(1);
+
+    app.on_key_event(
+        KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE),
+        &mut terminal,
+    )
+    .await?;
+    assert_eq!(std::fs::read_to_string(&out)?, state.tasks[&1].command);
+    assert_eq!(
+        app.status_message.as_deref(),
+        Some("Copied the command of task 1")
+    );
+    Ok(())
+}
+
+/// Test the text copied from the log viewer: the lines in view, or the last match jumped to
+#[test]
+fn test_log_state_copy_text() -> Result<()> {
+    let mut log_state = LogState::new(vec![1]);
+    for i in 1..=10 {
+        log_state.push_str(1, &format!("line {}\n", i));
+    }
+    log_state.push_str(1, "\x1b[31merror\x1b[0m: a long line that wraps\n");
+    log_state.set_highlights(log::compile_highlights(
+        &Config::default().highlight_rules(),
+    )?);
+    assert_eq!(log_state.match_text(), None);
+
+    log_state.handle_key(KeyEvent::new(KeyCode::Char('g'), KeyModifiers::NONE), 3, 20);
+    assert_eq!(log_state.visible_text(3, 20), "line 1\nline 2\nline 3");
+
+    // Wrapped lines take more than one row of the page
+    log_state.handle_key(KeyEvent::new(KeyCode::Char('E'), KeyModifiers::NONE), 3, 20);
+    assert_eq!(
+        log_state.visible_text(3, 20),
+        "line 10\nerror: a long line that wraps"
+    );
+    assert_eq!(
+        log_state.match_text().as_deref(),
+        Some("error: a long line that wraps")
+    );
+    Ok(())
+}
+
+/// Test scrolling to the end of a line too long to count its wrapped rows
+#[test]
+fn test_log_state_huge_wrapped_line() {
+    let mut log_state = LogState::new(vec![1]);
+    log_state.wrap = true;
+    log_state.push_str(1, &format!("{}\nlast\n", "x".repeat(200_000)));
+
+    log_state.handle_key(KeyEvent::new(KeyCode::Char('G'), KeyModifiers::NONE), 10, 2);
+    assert_eq!(log_state.visible_text(10, 2).lines().last(), Some("last"));
+    assert_eq!(log_state.gutter_lines(10, 2).len(), 10);
+}
+
+/// Test that a merged log keeps each task's partial lines apart and can hide tasks
+#[test]
+fn test_merged_log_interleaves_tasks() {
+    let mut log_state = LogState::new(vec![1, 2]);
+    log_state.push_str(1, "compiling a");
+    log_state.push_str(2, "compiling b\n");
+    log_state.push_str(1, ".rs\ndone\n");
+
+    assert_eq!(
+        log_state.lines().collect::<Vec<_>>(),
+        ["[1] compiling a.rs", "[2] compiling b", "[1] done"]
+    );
+    assert_eq!(
+        log_state.summary(),
+        "Merged Log #1 #2 | following | 3 lines, 32 B | 1-9: toggle"
+    );
+
+    // Toggle task 2 with its position in the view
+    let two = KeyEvent::new(KeyCode::Char('2'), KeyModifiers::NONE);
+    log_state.handle_key(two, 10, 40);
+    assert_eq!(
+        log_state.lines().collect::<Vec<_>>(),
+        ["[1] compiling a.rs", "[1] done"]
+    );
+    assert_eq!(log_state.visible_lines(10).0.len(), 2);
+    assert!(log_state.summary().contains("Merged Log #1 |"));
+    assert!(log_state.summary().contains("| hidden #2"));
+
+    log_state.handle_key(two, 10, 40);
+    assert_eq!(log_state.lines().count(), 3);
+}
+
+/// Test scrolling skips the lines of hidden tasks
+#[test]
+fn test_merged_log_scrolls_over_hidden_lines() {
+    let mut log_state = LogState::new(vec![1, 2]);
+    for i in 0..3 {
+        log_state.push_str(1, &format!("one {}\n", i));
+        log_state.push_str(2, &format!("two {}\n", i));
+    }
+    log_state.toggle_task(2);
+
+    let down = KeyEvent::new(KeyCode::Down, KeyModifiers::NONE);
+    log_state.handle_key(KeyEvent::new(KeyCode::Home, KeyModifiers::NONE), 2, 40);
+    log_state.handle_key(down, 2, 40);
+    assert_eq!(log_state.visible_lines(2).0[0].to_string(), "[1] one 1");
+
+    // Can't scroll past the last page of visible lines
+    log_state.handle_key(down, 2, 40);
+    log_state.handle_key(down, 2, 40);
+    let (lines, _) = log_state.visible_lines(2);
+    let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+    assert_eq!(lines, ["[1] one 1", "[1] one 2"]);
+}
+
+/// Test that Enter on a multi-selection opens a merged log of the selected tasks
+#[tokio::test]
+async fn test_multi_select_opens_merged_log() -> Result<()> {
+    use crate::{App, AppMode};
+
+    let (state, _, mut terminal, _) = setup_test_ui().await?;
+    let mock_client = MockPueueClient {
+        state: state.clone(),
+        log_delay: std::time::Duration::ZERO,
+        offline: Default::default(),
+    };
+    let mut app = App::new(mock_client, Config::default());
+    app.state = Some(state);
+    app.selected_task_ids = HashSet::from([1, 2]);
+
+    let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+    app.on_key_event(enter, &mut terminal).await?;
+
+    let AppMode::Log(log_state) = &app.app_mode else {
+        panic!("Expected log mode, got {:?}", app.app_mode);
+    };
+    assert_eq!(log_state.task_ids, [1, 2]);
+    assert_eq!(
+        log_state.lines().collect::<Vec<_>>(),
+        [
+            "[1] Full log of task 1",
+            "[1] Line 2",
+            "[1] Line 3",
+            "[2] Full log of task 2",
+            "[2] Line 2",
+            "[2] Line 3"
+        ]
+    );
+
+    Ok(())
+}
+
+/// Test that the preview follows the cursor, streaming running tasks
+#[tokio::test]
+async fn test_preview_follows_cursor() -> Result<()> {
+    use crate::{App, StreamEvent};
+
+    let (state, _, mut terminal, _) = setup_test_ui().await?;
+    let mock_client = MockPueueClient {
+        state: state.clone(),
+        log_delay: std::time::Duration::ZERO,
+        offline: Default::default(),
+    };
+    let mut app = App::new(mock_client, Config::default());
+    app.state = Some(state);
+
+    // Nothing to stream while the preview is off
+    let idle = tokio::time::timeout(
+        std::time::Duration::from_millis(50),
+        app.next_stream_event(),
+    )
+    .await;
+    assert!(idle.is_err());
+
+    let toggle = KeyEvent::new(KeyCode::Char('L'), KeyModifiers::SHIFT);
+    app.on_key_event(toggle, &mut terminal).await?;
+    terminal.draw(|f| app.draw(f))?;
+
+    // Task 0 is running, so its preview is streamed
+    let event = app.next_stream_event().await;
+    assert!(matches!(event, StreamEvent::PreviewLoaded(_, Some(_))));
+    app.on_stream_event(event);
+    let preview = app.preview.as_ref().unwrap();
+    assert_eq!(preview.task_id, 0);
+    assert!(preview.streaming);
+
+    // The mock closes streams straight away
+    let event = app.next_stream_event().await;
+    assert!(matches!(event, StreamEvent::Preview(Ok(None))));
+    app.on_stream_event(event);
+    assert!(!app.preview.as_ref().unwrap().streaming);
+
+    // Moving the cursor to a finished task fetches as much of its log as the pane shows
+    let down = KeyEvent::new(KeyCode::Down, KeyModifiers::NONE);
+    app.on_key_event(down, &mut terminal).await?;
+    let event = app.next_stream_event().await;
+    assert!(matches!(event, StreamEvent::PreviewLoaded(_, None)));
+    app.on_stream_event(event);
+    assert_eq!(app.preview.as_ref().unwrap().task_id, 1);
+
+    terminal.draw(|f| app.draw(f))?;
+    let ui = buffer_contents(terminal.backend().buffer());
+    assert!(ui.contains("┌ Preview: Task Log #1 | Success | 3 lines, 32 B ─"));
+    assert!(ui.contains("│Full log of task 1 "));
+
+    // Turning the preview off drops it
+    app.on_key_event(toggle, &mut terminal).await?;
+    app.on_key_event(toggle, &mut terminal).await?;
+    assert!(app.preview.is_none());
+
+    Ok(())
+}
+
+/// Test that a preview that takes longer to load than a tick isn't started over on every tick,
+/// and only fetches what fits in the pane
+#[tokio::test]
+async fn test_slow_preview_survives_ticks() -> Result<()> {
+    use crate::{App, PreviewLayout, StreamEvent};
+
+    let (state, _, _, _) = setup_test_ui().await?;
+    let mock_client = MockPueueClient {
+        state: state.clone(),
+        offline: Default::default(),
+        log_delay: std::time::Duration::from_millis(300),
+    };
+    let mut app = App::new(mock_client, Config::default());
+    app.state = Some(state);
+    app.preview_layout = PreviewLayout::Bottom;
+    app.current_task_id = Some(1);
+    app.preview_height = 2;
+
+    // Like the main loop, where the tick interrupts the wait for the preview
+    let mut ticks = 0;
+    let event = loop {
+        tokio::select! {
+            event = app.next_stream_event() => break event,
+            _ = tokio::time::sleep(std::time::Duration::from_millis(100)) => {
+                ticks += 1;
+                app.refresh_state().await?;
+            }
+        }
+        assert!(ticks < 10, "The preview never loaded");
+    };
+    assert!(ticks >= 2);
+    assert!(matches!(event, StreamEvent::PreviewLoaded(_, None)));
+    app.on_stream_event(event);
+
+    let preview = app.preview.as_ref().unwrap();
+    assert_eq!(preview.task_id, 1);
+    assert_eq!(preview.lines().collect::<Vec<_>>(), ["Line 2", "Line 3"]);
+    assert!(!preview.output_complete);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ui_snapshot_preview_right() -> Result<()> {
+    let (state, task_ids, mut terminal, jiff_now) = setup_test_ui().await?;
+    let mut table_state = TableState::default();
+    table_state.select(Some(0));
+
+    let mut preview = LogState::new(vec![0]);
+    preview.push_str(
+        0,
+        "step 1\nstep 2\nstep 3 is a long line that wraps in the preview\n",
+    );
+
+    terminal.draw(|f| {
+        let mut ui_state = ui::UiState {
+            state: &Some(state),
+            table_state: &mut table_state,
+            task_ids: &task_ids,
+            now: jiff_now,
+            show_details: false,
+            details_scroll_offset: 0,
+            filter_text: "",
+            group_filter: None,
+            status_filter: None,
+            daemon: None,
+            daemons: &[],
+            input_mode: false,
+            sort_mode: false,
+            sort_field: SortField::default(),
+            log_view: None,
+            connection_error: None,
+            error_modal: None,
+            selected_task_ids: &HashSet::new(),
+            help_mode: false,
+            help_scroll_offset: 0,
+            group_picker: None,
+            clean_options: None,
+            confirm_reset: false,
+            status_message: None,
+            deps_view: None,
+            env_view: None,
+            history: None,
+            history_view: None,
+            events_view: None,
+            preview: Some((PreviewLayout::Right, Some(&preview))),
+            custom_commands: &BTreeMap::new(),
+            config_path: None,
+        };
+        ui::draw(f, &mut ui_state);
+    })?;
+
+    let ui = buffer_contents(terminal.backend().buffer());
+
+    insta::assert_snapshot!(ui);
+
+    Ok(())
+}
+
+/// Test that the tasks of several daemons are merged with namespaced ids, and that
+/// requests are routed back to the daemon the tasks belong to
+#[tokio::test]
+async fn test_daemons_merge_and_route() -> Result<()> {
+    use crate::daemons::{DaemonStatus, Daemons, ID_STRIDE};
+
+    let default_group = || {
+        let mut client = MockPueueClient::new();
+        client.state.groups.insert(
+            "default".to_string(),
+            Group {
+                status: GroupStatus::Running,
+                parallel_tasks: 1,
+            },
+        );
+        client
+    };
+    let mut second = default_group();
+    second.state.tasks.get_mut(&2).unwrap().dependencies = vec![1];
+    let mut client = Daemons::new(vec![
+        ("local".to_string(), default_group()),
+        ("build".to_string(), second),
+    ]);
+
+    let state = client.get_state().await?;
+    let ids: Vec<usize> = state.tasks.keys().copied().collect();
+    assert_eq!(ids, vec![0, 1, 2, ID_STRIDE, ID_STRIDE + 1, ID_STRIDE + 2]);
+    assert_eq!(state.tasks[&(ID_STRIDE + 2)].id, ID_STRIDE + 2);
+    assert_eq!(
+        state.tasks[&(ID_STRIDE + 2)].dependencies,
+        vec![ID_STRIDE + 1]
+    );
+    // Each daemon's groups are kept apart
+    let groups: Vec<&str> = state.groups.keys().map(String::as_str).collect();
+    assert_eq!(groups, vec!["build/default", "local/default"]);
+    assert_eq!(state.tasks[&(ID_STRIDE + 2)].group, "build/default");
+    assert!(crate::daemons::group_matches("default", "build/default"));
+    assert!(crate::daemons::group_matches(
+        "build/default",
+        "build/default"
+    ));
+    assert!(!crate::daemons::group_matches(
+        "local/default",
+        "build/default"
+    ));
+
+    // Only the second daemon's task 1 is removed
+    client.remove_tasks(vec![ID_STRIDE + 1]).await?;
+    let ids: Vec<usize> = client.get_state().await?.tasks.keys().copied().collect();
+    assert_eq!(ids, vec![0, 1, 2, ID_STRIDE, ID_STRIDE + 2]);
+
+    // Tasks can only be swapped within a daemon
+    assert!(client.switch_tasks(0, ID_STRIDE).await.is_err());
+
+    // New tasks go to the daemon of the task they were made from
+    let request = AddRequest {
+        command: "make".to_string(),
+        path: PathBuf::from("/tmp"),
+        envs: HashMap::new(),
+        start_immediately: false,
+        stashed: false,
+        group: "build/default".to_string(),
+        enqueue_at: None,
+        dependencies: vec![0, ID_STRIDE],
+        priority: None,
+        label: None,
+    };
+    let id = client.add_task(ID_STRIDE, request.clone()).await?;
+    assert_eq!(id, ID_STRIDE + 3);
+    let state = client.get_state().await?;
+    assert_eq!(state.tasks[&id].dependencies, vec![ID_STRIDE]);
+    assert_eq!(state.tasks[&id].group, "build/default");
+    // but not to another daemon's group
+    let request = AddRequest {
+        group: "local/default".to_string(),
+        ..request
+    };
+    assert!(client.add_task(ID_STRIDE, request).await.is_err());
+
+    // Cleaning a group only cleans its daemon
+    client
+        .clean_tasks(false, Some("build/default".to_string()))
+        .await?;
+    let ids: Vec<usize> = client.get_state().await?.tasks.keys().copied().collect();
+    assert_eq!(ids, vec![0, 1, 2, ID_STRIDE, ID_STRIDE + 3]);
+
+    // A connection for one task only connects to that task's daemon
+    let mut task_client = client.new(ID_STRIDE + 2).await?;
+    let log = task_client.get_log(ID_STRIDE + 2, None).await?;
+    assert!(log.output.starts_with("Full log of task 2"));
+    let error = task_client.get_log(2, None).await.unwrap_err();
+    assert_eq!(error.to_string(), "Not connected to local");
+
+    assert_eq!(
+        client.daemon_status(),
+        vec![
+            DaemonStatus {
+                name: "local".to_string(),
+                error: None,
+            },
+            DaemonStatus {
+                name: "build".to_string(),
+                error: None,
+            },
+        ]
+    );
+
+    Ok(())
+}
+
+/// Test that a lone daemon's task ids are used as they are, even past the id stride
+#[tokio::test]
+async fn test_single_daemon_ids_unchanged() -> Result<()> {
+    use crate::daemons::{Daemons, ID_STRIDE};
+
+    let mut mock = MockPueueClient::new();
+    let mut task = mock.state.tasks.remove(&2).unwrap();
+    task.id = ID_STRIDE + 5;
+    mock.state.tasks.insert(ID_STRIDE + 5, task);
+    let mut client = Daemons::new(vec![("default".to_string(), mock)]);
+
+    let ids: Vec<usize> = client.get_state().await?.tasks.keys().copied().collect();
+    assert_eq!(ids, vec![0, 1, ID_STRIDE + 5]);
+    let log = client.get_log(ID_STRIDE + 5, None).await?;
+    assert!(log.output.starts_with("Full log of task 1000005"));
+    let mut task_client = client.new(ID_STRIDE + 5).await?;
+    assert!(task_client.get_log(ID_STRIDE + 5, None).await.is_ok());
+    client.remove_tasks(vec![ID_STRIDE + 5]).await?;
+    let ids: Vec<usize> = client.get_state().await?.tasks.keys().copied().collect();
+    assert_eq!(ids, vec![0, 1]);
+
+    let daemons = client.daemon_status();
+    assert_eq!(ui::task_label(&daemons, ID_STRIDE + 5), "1000005");
+
+    Ok(())
+}
+
+/// Test that tasks of several daemons are shown with their id on their own daemon
+#[tokio::test]
+async fn test_daemons_task_labels() -> Result<()> {
+    use crate::daemons::{Daemons, ID_STRIDE};
+
+    let mut second = MockPueueClient::new();
+    second.state.tasks.get_mut(&2).unwrap().dependencies = vec![1];
+    let mut client = Daemons::new(vec![
+        ("local".to_string(), MockPueueClient::new()),
+        ("build".to_string(), second),
+    ]);
+    let state = client.get_state().await?;
+    let daemons = client.daemon_status();
+    assert_eq!(ui::task_label(&daemons, ID_STRIDE + 2), "2@build");
+    assert_eq!(ui::task_label(&daemons[..1], 2), "2");
+
+    let mut log_state = LogState::new(vec![2, ID_STRIDE + 2]);
+    log_state.set_labels(&daemons);
+    log_state.push_str(ID_STRIDE + 2, "done\n");
+    assert!(
+        log_state
+            .summary()
+            .starts_with("Merged Log #2@local #2@build")
+    );
+    assert_eq!(
+        log_state.lines().collect::<Vec<_>>(),
+        vec!["[2@build] done"]
+    );
+
+    let mut log_state = LogState::new(vec![ID_STRIDE + 2]);
+    log_state.set_labels(&daemons);
+    assert!(log_state.summary().starts_with("Task Log #2@build"));
+    assert_eq!(log_state.file_label(), "2-build");
+    let file = log_state.save_temporary()?;
+    let name = file.path().file_name().unwrap().to_string_lossy();
+    assert!(name.starts_with("pui-task-2-build-"), "{}", name);
+
+    let task = &state.tasks[&(ID_STRIDE + 2)];
+    let header = log::log_header(&log_state.label(ID_STRIDE + 2), task);
+    assert!(header.starts_with("# Task: 2@build\n"), "{}", header);
+    let details = ui::build_details_text(
+        ID_STRIDE + 2,
+        task,
+        &state,
+        &daemons,
+        &jiff::Timestamp::now(),
+        None,
+    );
+    assert!(details.starts_with("ID: 2@build\n"), "{}", details);
+    assert!(details.contains("  #1@build "), "{}", details);
+
+    Ok(())
+}
+
+/// Test that the tasks of a daemon that comes back aren't recorded as added
+#[tokio::test]
+async fn test_daemon_back_online_is_baseline() -> Result<()> {
+    use crate::App;
+    use crate::daemons::Daemons;
+    use std::sync::atomic::Ordering;
+
+    let build = MockPueueClient::new();
+    let offline = build.offline.clone();
+    let client = Daemons::new(vec![
+        ("local".to_string(), MockPueueClient::new()),
+        ("build".to_string(), build),
+    ]);
+    let mut app = App::new(client, Config::default());
+    app.refresh_state().await?;
+    assert_eq!(app.state.as_ref().unwrap().tasks.len(), 6);
+
+    offline.store(true, Ordering::Relaxed);
+    app.refresh_state().await?;
+    assert_eq!(app.state.as_ref().unwrap().tasks.len(), 3);
+    offline.store(false, Ordering::Relaxed);
+    app.refresh_state().await?;
+    assert_eq!(app.state.as_ref().unwrap().tasks.len(), 6);
+    let events: Vec<&str> = app
+        .events
+        .filtered(None)
+        .map(|event| event.message.as_str())
+        .collect();
+    assert!(events.is_empty(), "{:?}", events);
+
+    Ok(())
+}
+
+/// Test that a daemon that can't be reached doesn't stop the others being cleaned or reset,
+/// and that the reset confirmation names every daemon
+#[tokio::test]
+async fn test_daemons_clean_and_reset_past_failures() -> Result<()> {
+    use crate::App;
+    use crate::daemons::{Daemons, ID_STRIDE};
+
+    let client = Daemons::new(vec![
+        ("local".to_string(), MockPueueClient::new()),
+        ("build".to_string(), MockPueueClient::new()),
+    ]);
+    // A connection for a task of the build daemon isn't connected to the local one
+    let mut client = client.new(ID_STRIDE).await?;
+
+    let error = client.clean_tasks(false, None).await.unwrap_err();
+    assert_eq!(error.to_string(), "local: Not connected");
+    let ids: Vec<usize> = client.get_state().await?.tasks.keys().copied().collect();
+    assert_eq!(ids, vec![ID_STRIDE]);
+    let error = client.reset().await.unwrap_err();
+    assert_eq!(error.to_string(), "local: Not connected");
+    assert!(client.get_state().await?.tasks.is_empty());
+
+    let client = Daemons::new(vec![
+        ("local".to_string(), MockPueueClient::new()),
+        ("build".to_string(), MockPueueClient::new()),
+    ]);
+    let mut app = App::new(client, Config::default());
+    app.refresh_state().await?;
+    let backend = TestBackend::new(80, 24);
+    let mut terminal = Terminal::new(backend)?;
+    for code in [KeyCode::Char('C'), KeyCode::Char('R')] {
+        app.on_key_event(KeyEvent::new(code, KeyModifiers::NONE), &mut terminal)
+            .await?;
+    }
+    terminal.draw(|f| app.draw(f))?;
+    let ui = buffer_contents(terminal.backend().buffer());
+    assert!(ui.contains("Reset daemons"), "{}", ui);
+    assert!(ui.contains("on local, build"), "{}", ui);
+
+    Ok(())
+}
+
+/// Test that with several daemons the task list shows each task's host, with its own id,
+/// and that the filter matches host names
+#[tokio::test]
+async fn test_task_list_hosts() -> Result<()> {
+    use crate::App;
+    use crate::daemons::Daemons;
+
+    let client = Daemons::new(vec![
+        ("local".to_string(), MockPueueClient::new()),
+        ("build".to_string(), MockPueueClient::new()),
+    ]);
+    let mut app = App::new(client, Config::default());
+
+    let text = app.list("build", SortField::Id, false).await?;
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].contains("Host"));
+    assert!(lines[1..].iter().all(|line| line.contains("build")));
+    assert!(lines[1].trim_start().starts_with('0'));
+
+    Ok(())
+}
+
+/// Test which status transitions are notified about, and what the notifications say
+#[test]
+fn test_notify_finished_tasks() {
+    use crate::config::{NotificationConfig, NotifyMethod, ResultFilter};
+    use crate::notify::{self, Notification};
+
+    let old = MockPueueClient::new().state;
+    let mut new = old.clone();
+    let now = Local.timestamp_opt(1767225600, 0).unwrap();
+    // Task 0 fails, the already finished tasks 1 and 2 don't count again
+    new.tasks.get_mut(&0).unwrap().status = TaskStatus::Done {
+        enqueued_at: now,
+        start: now,
+        end: now,
+        result: TaskResult::Failed(1),
+    };
+    assert_eq!(notify::finished_tasks(&old, &new), vec![0]);
+    assert!(notify::finished_tasks(&new, &new).is_empty());
+
+    let task = &new.tasks[&0];
+    let mut config = NotificationConfig::default();
+    assert!(notify::wanted(&config, task));
+    config.results = vec![ResultFilter::Success];
+    assert!(!notify::wanted(&config, task));
+    config.results = vec![ResultFilter::Failure];
+    config.groups = vec!["gpu".to_string()];
+    assert!(!notify::wanted(&config, task));
+    config.groups = vec!["default".to_string()];
+    config.labels = vec!["train".to_string()];
+    assert!(!notify::wanted(&config, task));
+    config.labels.clear();
+    assert!(notify::wanted(&config, task));
+
+    let notification = Notification::new(0, task, &[]);
+    assert_eq!(notification.title, "Task 0: Failed (1)");
+    assert_eq!(notification.message, "sleep 60");
+    let daemons = ["local", "build"].map(|name| crate::daemons::DaemonStatus {
+        name: name.to_string(),
+        error: None,
+    });
+    assert_eq!(
+        Notification::new(crate::daemons::ID_STRIDE, task, &daemons).title,
+        "Task 0 on build: Failed (1)"
+    );
+    // A lone daemon's ids are its own, however high
+    assert_eq!(
+        Notification::new(crate::daemons::ID_STRIDE, task, &daemons[..1]).title,
+        "Task 1000000: Failed (1)"
+    );
+    assert_eq!(
+        notify::sequence(NotifyMethod::Bell, &notification, false).as_deref(),
+        Some("\x07")
+    );
+    assert_eq!(
+        notify::sequence(NotifyMethod::Osc9, &notification, false).as_deref(),
+        Some("\x1b]9;Task 0: Failed (1): sleep 60\x07")
+    );
+    assert_eq!(
+        notify::sequence(NotifyMethod::Osc777, &notification, true).as_deref(),
+        Some("\x1bPtmux;\x1b\x1b]777;notify;Task 0: Failed (1);sleep 60\x07\x1b\\")
+    );
+    assert_eq!(
+        notify::sequence(NotifyMethod::Command, &notification, false),
+        None
+    );
+}
+
+/// Test that a refresh which sees a task finish runs the notifier command
+#[tokio::test]
+async fn test_notification_command_on_refresh() -> Result<()> {
+    use crate::App;
+    use crate::config::{NotificationConfig, NotifyMethod};
+
+    let dir = tempfile::tempdir()?;
+    let out = dir.path().join("notifications");
+    let config = Config {
+        notifications: Some(NotificationConfig {
+            method: NotifyMethod::Command,
+            command: Some(vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                format!("printf '%s|%s\\n' \"$0\" \"$1\" >> {}", out.display()),
+            ]),
+            ..NotificationConfig::default()
+        }),
+        ..Config::default()
+    };
+    let mut app = App::new(MockPueueClient::new(), config);
+
+    // The first refresh has nothing to compare with
+    app.refresh_state().await?;
+    let now = Local.timestamp_opt(1767225600, 0).unwrap();
+    app.pueue_client.state.tasks.get_mut(&0).unwrap().status = TaskStatus::Done {
+        enqueued_at: now,
+        start: now,
+        end: now,
+        result: TaskResult::Success,
+    };
+    app.refresh_state().await?;
+    app.refresh_state().await?;
+
+    let mut contents = String::new();
+    for _ in 0..100 {
+        contents = std::fs::read_to_string(&out).unwrap_or_default();
+        if !contents.is_empty() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert_eq!(contents, "Task 0: Success|sleep 60\n");
+    assert_eq!(app.status_message, None);
+
+    Ok(())
+}
+
+/// Test which status changes hooks run on, and how their commands are filled in
+#[test]
+fn test_hooks_match_and_command_line() -> Result<()> {
+    use crate::config::{HookConfig, StatusMatcher};
+    use crate::hooks;
+
+    let old = MockPueueClient::new().state;
+    let mut new = old.clone();
+    let now = Local.timestamp_opt(1767225600, 0).unwrap();
+    new.tasks.get_mut(&0).unwrap().status = TaskStatus::Done {
+        enqueued_at: now,
+        start: now,
+        end: now,
+        result: TaskResult::Failed(1),
+    };
+    new.tasks.get_mut(&0).unwrap().label = Some("nightly".to_string());
+    assert_eq!(crate::events::status_changes(&old, &new), vec![0]);
+    assert!(crate::events::status_changes(&new, &new).is_empty());
+
+    let hook = |on, from| HookConfig {
+        on,
+        from,
+        groups: Vec::new(),
+        labels: Vec::new(),
+        command: None,
+        run: vec!["./rollback.sh".to_string(), "{id}".to_string()],
+    };
+    let (old_task, new_task) = (&old.tasks[&0], &new.tasks[&0]);
+    let matches = |config: HookConfig| -> Result<bool> {
+        Ok(hooks::compile_hooks(&[config])?[0].matches(old_task, new_task))
+    };
+    assert!(matches(hook(StatusMatcher::Failure, None))?);
+    assert!(matches(hook(
+        StatusMatcher::Done,
+        Some(StatusMatcher::Running)
+    ))?);
+    assert!(!matches(hook(StatusMatcher::Success, None))?);
+    assert!(!matches(hook(
+        StatusMatcher::Done,
+        Some(StatusMatcher::Queued)
+    ))?);
+    assert!(!matches(HookConfig {
+        groups: vec!["deploy".to_string()],
+        ..hook(StatusMatcher::Failure, None)
+    })?);
+    assert!(matches(HookConfig {
+        labels: vec!["nightly".to_string()],
+        command: Some("^sleep".to_string()),
+        ..hook(StatusMatcher::Failure, None)
+    })?);
+    assert!(!matches(HookConfig {
+        command: Some("^cargo".to_string()),
+        ..hook(StatusMatcher::Failure, None)
+    })?);
+
+    let hooks = hooks::compile_hooks(&[HookConfig {
+        run: vec![
+            "notify".to_string(),
+            "{id} {group} {label} {status}".to_string(),
+            "{command} in {path}".to_string(),
+        ],
+        ..hook(StatusMatcher::Done, None)
+    }])?;
+    assert_eq!(
+        hooks[0].command_line(
+            crate::daemons::ID_STRIDE,
+            new_task,
+            &["local", "build"].map(|name| crate::daemons::DaemonStatus {
+                name: name.to_string(),
+                error: None,
+            })
+        ),
+        vec!["notify", "0 default nightly Failed (1)", "sleep 60 in /tmp"]
+    );
+    // Placeholders are filled in once, not again inside the values they're filled with
+    new.tasks.get_mut(&0).unwrap().label = Some("{id} {path}".to_string());
+    let hooks = hooks::compile_hooks(&[HookConfig {
+        run: vec!["notify".to_string(), "{label}:{id} {other} {".to_string()],
+        ..hook(StatusMatcher::Done, None)
+    }])?;
+    assert_eq!(
+        hooks[0].command_line(0, &new.tasks[&0], &[]),
+        vec!["notify", "{id} {path}:0 {other} {"]
+    );
+    assert!(hooks::compile_hooks(&[hook(StatusMatcher::Done, None)]).is_ok());
+
+    // Hooks that can't be compiled aren't dropped silently
+    let config = Config {
+        hooks: vec![HookConfig {
+            run: Vec::new(),
+            ..hook(StatusMatcher::Done, None)
+        }],
+        ..Config::default()
+    };
+    let app = crate::App::new(MockPueueClient::new(), config);
+    assert!(app.hooks.is_empty());
+    assert_eq!(
+        app.status_message.as_deref(),
+        Some("Hooks disabled: Hook on Done has nothing to run")
+    );
+    Ok(())
+}
+
+/// Test that a hook keeps running after pui (and with it the runtime) has quit
+#[test]
+fn test_hooks_outlive_app() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let marker = dir.path().join("done");
+    let (sender, _receiver) = tokio::sync::mpsc::unbounded_channel();
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        crate::hooks::spawn(
+            0,
+            vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                format!("sleep 0.3; touch {}", marker.display()),
+            ],
+            None,
+            String::new(),
+            sender,
+        );
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    });
+    drop(runtime);
+
+    for _ in 0..50 {
+        if marker.exists() {
+            return Ok(());
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    panic!("The hook was stopped when the runtime shut down");
+}
+
+/// Test that hooks run in the background when a refresh sees a task change status, are
+/// given the task as JSON, and that their results end up in the event panel
+#[tokio::test]
+async fn test_hooks_run_on_refresh() -> Result<()> {
+    use crate::App;
+    use crate::config::{HookConfig, StatusMatcher};
+
+    let dir = tempfile::tempdir()?;
+    let input = dir.path().join("input.json");
+    let hook = |run: Vec<String>| HookConfig {
+        on: StatusMatcher::Success,
+        from: Some(StatusMatcher::Running),
+        groups: Vec::new(),
+        labels: Vec::new(),
+        command: None,
+        run,
+    };
+    let config = Config {
+        hooks: vec![
+            hook(vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                format!("cat > {}", input.display()),
+            ]),
+            hook(vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                "echo 'no rollback for {id}' >&2; exit 3".to_string(),
+            ]),
+        ],
+        ..Config::default()
+    };
+    let mut app = App::new(MockPueueClient::new(), config);
+
+    app.refresh_state().await?;
+    let now = Local.timestamp_opt(1767225600, 0).unwrap();
+    app.pueue_client.state.tasks.get_mut(&0).unwrap().status = TaskStatus::Done {
+        enqueued_at: now,
+        start: now,
+        end: now,
+        result: TaskResult::Success,
+    };
+    app.refresh_state().await?;
+    app.refresh_state().await?;
+
+    for _ in 0..100 {
+        app.collect_events();
+        let hooks_done = app
+            .events
+            .filtered(None)
+            .filter(|event| event.message.starts_with("Hook"))
+            .count();
+        if hooks_done == 2 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    let mut messages: Vec<(String, bool)> = app
+        .events
+        .filtered(None)
+        .filter(|event| event.message.starts_with("Hook"))
+        .map(|event| {
+            assert_eq!(event.task_id, Some(0));
+            (event.message.clone(), event.error)
+        })
+        .collect();
+    messages.sort();
+    assert_eq!(
+        messages,
+        vec![
+            (
+                format!("Hook `sh -c cat > {}` succeeded", input.display()),
+                false
+            ),
+            (
+                "Hook `sh -c echo 'no rollback for 0' >&2; exit 3` failed (exit code 3): no rollback for 0"
+                    .to_string(),
+                true
+            ),
+        ]
+    );
+
+    let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&input)?)?;
+    assert_eq!(json["id"], 0);
+    assert_eq!(json["previous_status"], "Running");
+    assert_eq!(json["status"], "Success");
+    assert_eq!(json["full_command"], "sleep 60");
+
+    Ok(())
+}
+
+/// Test the event panel
+#[tokio::test]
+async fn test_ui_snapshot_events() -> Result<()> {
+    use crate::events::{Event, EventLog, EventsState};
+
+    let (state, task_ids, mut terminal, jiff_now) = setup_test_ui().await?;
+    let mut table_state = TableState::default();
+    table_state.select(Some(0));
+    let mut events = EventLog::default();
+    events.push(Event {
+        at: jiff_now,
+        task_id: Some(2),
+        message: "Hook `./rollback.sh 2` failed (exit code 1): no previous release".to_string(),
+        error: true,
+    });
+    events.push(Event {
+        at: jiff_now,
+        task_id: Some(1),
+        message: "Hook `./report.sh` succeeded".to_string(),
+        error: false,
+    });
+
+    terminal.draw(|f| {
+        let mut ui_state = ui::UiState {
+            state: &Some(state),
+            table_state: &mut table_state,
+            task_ids: &task_ids,
+            now: jiff_now,
+            show_details: false,
+            details_scroll_offset: 0,
+            filter_text: "",
+            group_filter: None,
+            status_filter: None,
+            daemon: None,
+            daemons: &[],
+            input_mode: false,
+            sort_mode: false,
+            sort_field: SortField::default(),
+            log_view: None,
+            connection_error: None,
+            error_modal: None,
+            selected_task_ids: &HashSet::new(),
+            help_mode: false,
+            help_scroll_offset: 0,
+            group_picker: None,
+            clean_options: None,
+            confirm_reset: false,
+            status_message: None,
+            deps_view: None,
+            env_view: None,
+            history: None,
+            history_view: None,
+            events_view: Some((
+                &events,
+                &EventsState {
+                    selected: 1,
+                    task_filter: None,
+                },
+            )),
+            preview: None,
+            custom_commands: &BTreeMap::new(),
+            config_path: None,
+        };
+        ui::draw(f, &mut ui_state);
+    })?;
+
+    let ui = buffer_contents(terminal.backend().buffer());
+
+    insta::assert_snapshot!(ui);
+
+    Ok(())
+}
+
+/// Test the events recorded for the changes between two states
+#[test]
+fn test_task_changes() {
+    use crate::events;
+
+    let old = MockPueueClient::new().state;
+    let mut new = old.clone();
+    let then = Local.timestamp_opt(1767225600, 0).unwrap();
+    let now = jiff::Timestamp::from_second(1767225660).unwrap();
+    // Task 0 finishes, task 1 is removed and task 3 is added
+    new.tasks.get_mut(&0).unwrap().status = TaskStatus::Done {
+        enqueued_at: then,
+        start: then,
+        end: then,
+        result: TaskResult::Failed(2),
+    };
+    new.tasks.remove(&1);
+    let mut added = new.tasks[&2].clone();
+    added.id = 3;
+    added.command = "make".to_string();
+    added.status = TaskStatus::Queued { enqueued_at: then };
+    new.tasks.insert(3, added);
+
+    let changes = events::task_changes(&old, &new, now, |_| false);
+    let summary: Vec<(Option<usize>, &str, bool)> = changes
+        .iter()
+        .map(|event| (event.task_id, event.message.as_str(), event.error))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (Some(0), "Finished: Failed (2)", true),
+            (Some(3), "Added (Queued): make", false),
+            (Some(1), "Removed: echo 'hello'", false),
+        ]
+    );
+    // Finishing is timed by the daemon
+    assert_eq!(changes[0].at.as_second(), 1767225600);
+    assert_eq!(changes[1].at, now);
+
+    // Tasks of a daemon that can't be reached aren't removed
+    let changes = events::task_changes(&old, &new, now, |_| true);
+    assert!(
+        changes
+            .iter()
+            .all(|event| !event.message.starts_with("Removed"))
+    );
+
+    // Pausing and resuming
+    let mut paused = old.clone();
+    paused.tasks.get_mut(&0).unwrap().status = TaskStatus::Paused {
+        enqueued_at: then,
+        start: then,
+    };
+    let messages = |old: &State, new: &State| -> Vec<String> {
+        events::task_changes(old, new, now, |_| false)
+            .into_iter()
+            .map(|event| event.message)
+            .collect()
+    };
+    assert_eq!(messages(&old, &paused), vec!["Paused"]);
+    assert_eq!(messages(&paused, &old), vec!["Resumed"]);
+}
+
+/// Test that refreshes fill the event log, which can be narrowed down to one task
+#[tokio::test]
+async fn test_event_panel_filters_by_task() -> Result<()> {
+    use crate::App;
+
+    let mut app = App::new(MockPueueClient::new(), Config::default());
+    let backend = TestBackend::new(80, 24);
+    let mut terminal = Terminal::new(backend)?;
+
+    app.refresh_state().await?;
+    app.pueue_client.state.tasks.get_mut(&2).unwrap().status = TaskStatus::Queued {
+        enqueued_at: Local.timestamp_opt(1767225600, 0).unwrap(),
+    };
+    app.pueue_client.state.tasks.remove(&1);
+    app.refresh_state().await?;
+    let messages: Vec<&str> = app
+        .events
+        .filtered(None)
+        .map(|event| event.message.as_str())
+        .collect();
+    assert_eq!(messages, vec!["Removed: echo 'hello'", "Queued"]);
+
+    // Highlight the requeue, only show task 2's events, then all events again
+    for code in [KeyCode::Char('E'), KeyCode::Char('j'), KeyCode::Char('t')] {
+        app.on_key_event(KeyEvent::new(code, KeyModifiers::NONE), &mut terminal)
+            .await?;
+    }
+    terminal.draw(|f| app.draw(f))?;
+    let ui = buffer_contents(terminal.backend().buffer());
+    assert!(ui.contains("Events of task 2 (1)"), "{}", ui);
+    assert!(!ui.contains("Removed"));
+
+    app.on_key_event(
+        KeyEvent::new(KeyCode::Char('t'), KeyModifiers::NONE),
+        &mut terminal,
+    )
+    .await?;
+    terminal.draw(|f| app.draw(f))?;
+    assert!(buffer_contents(terminal.backend().buffer()).contains("Events (2)"));
+
+    for code in [KeyCode::Char('j'), KeyCode::Enter] {
+        app.on_key_event(KeyEvent::new(code, KeyModifiers::NONE), &mut terminal)
+            .await?;
+    }
+    assert_eq!(app.current_task_id, Some(2));
+
+    Ok(())
+}
+
+/// Test the summary header: counts per status, running tasks against each group's limit,
+/// and the total runtime of running tasks
+#[tokio::test]
+async fn test_ui_snapshot_summary_header() -> Result<()> {
+    let (mut state, task_ids, mut terminal, jiff_now) = setup_test_ui().await?;
+    state.groups.insert(
+        "default".to_string(),
+        Group {
+            status: GroupStatus::Running,
+            parallel_tasks: 2,
+        },
+    );
+    state.groups.insert(
+        "gpu".to_string(),
+        Group {
+            status: GroupStatus::Paused,
+            parallel_tasks: 1,
+        },
+    );
+    let started = Local.timestamp_opt(1767225600 - 3723, 0).unwrap();
+    state.tasks.get_mut(&0).unwrap().status = TaskStatus::Running {
+        enqueued_at: started,
+        start: started,
+    };
+    let mut table_state = TableState::default();
+    table_state.select(Some(0));
+
+    terminal.draw(|f| {
+        let mut ui_state = ui::UiState {
+            state: &Some(state),
+            table_state: &mut table_state,
+            task_ids: &task_ids,
+            now: jiff_now,
+            show_details: false,
+            details_scroll_offset: 0,
+            filter_text: "",
+            group_filter: None,
+            status_filter: Some(crate::config::StatusMatcher::Failure),
+            daemon: None,
+            daemons: &[],
+            input_mode: false,
+            sort_mode: false,
+            sort_field: SortField::default(),
+            log_view: None,
+            connection_error: None,
+            error_modal: None,
+            selected_task_ids: &HashSet::new(),
+            help_mode: false,
+            help_scroll_offset: 0,
+            group_picker: None,
+            clean_options: None,
+            confirm_reset: false,
+            status_message: None,
+            deps_view: None,
+            env_view: None,
+            history: None,
+            history_view: None,
+            events_view: None,
+            preview: None,
+            custom_commands: &BTreeMap::new(),
+            config_path: None,
+        };
+        ui::draw(f, &mut ui_state);
+    })?;
+
+    let ui = buffer_contents(terminal.backend().buffer());
+
+    insta::assert_snapshot!(ui);
+
+    Ok(())
+}
+
+/// Test that Tab goes through the statuses counted in the header, and `>` through the groups
+#[tokio::test]
+async fn test_summary_filters() -> Result<()> {
+    use crate::App;
+
+    let mut mock_client = MockPueueClient::new();
+    mock_client.state.tasks.get_mut(&2).unwrap().group = "gpu".to_string();
+    for group in ["default", "gpu"] {
+        mock_client.state.groups.insert(
+            group.to_string(),
+            Group {
+                status: GroupStatus::Running,
+                parallel_tasks: 1,
+            },
+        );
+    }
+    let mut app = App::new(mock_client, Config::default());
+    app.refresh_state().await?;
+    let backend = TestBackend::new(80, 24);
+    let mut terminal = Terminal::new(backend)?;
+    let mut press = async |app: &mut App<MockPueueClient>, code| -> Result<Vec<usize>> {
+        app.on_key_event(KeyEvent::new(code, KeyModifiers::NONE), &mut terminal)
+            .await?;
+        Ok(app.get_filtered_task_ids())
+    };
+
+    // Backwards: succeeded, then failed, then on to the statuses without tasks
+    assert_eq!(press(&mut app, KeyCode::BackTab).await?, vec![1]);
+    assert_eq!(press(&mut app, KeyCode::BackTab).await?, vec![2]);
+    assert_eq!(app.current_task_id, Some(2));
+    assert_eq!(
+        press(&mut app, KeyCode::BackTab).await?,
+        Vec::<usize>::new()
+    );
+    // Forwards from the last status shows all
+    press(&mut app, KeyCode::Tab).await?;
+    press(&mut app, KeyCode::Tab).await?;
+    assert_eq!(press(&mut app, KeyCode::Tab).await?, vec![0, 1, 2]);
+
+    assert_eq!(press(&mut app, KeyCode::Char('>')).await?, vec![0, 1]);
+    assert_eq!(press(&mut app, KeyCode::Char('>')).await?, vec![2]);
+    assert_eq!(press(&mut app, KeyCode::Char('>')).await?, vec![0, 1, 2]);
+
+    // Esc clears the status filter before the group filter
+    press(&mut app, KeyCode::Char('>')).await?;
+    assert_eq!(press(&mut app, KeyCode::Tab).await?, vec![0]);
+    assert_eq!(press(&mut app, KeyCode::Esc).await?, vec![0, 1]);
+    assert_eq!(press(&mut app, KeyCode::Esc).await?, vec![0, 1, 2]);
+
+    Ok(())
+}
+
+/// Test that the task list's own keys come before custom commands, and that custom keys are
+/// case sensitive
+#[tokio::test]
+async fn test_custom_command_keys() -> Result<()> {
+    use crate::config::CustomCommand;
+    use crate::{App, AppMode};
+
+    let dir = tempfile::tempdir()?;
+    let touch = |name: &str, key: &str| CustomCommand {
+        key: key.to_string(),
+        cmd: vec![
+            "touch".to_string(),
+            dir.path().join(name).display().to_string(),
+        ],
+    };
+    let mut config = Config::default();
+    config
+        .custom_commands
+        .insert("mark".to_string(), touch("mark", "G"));
+    config
+        .custom_commands
+        .insert("events".to_string(), touch("events", "E"));
+    assert_eq!(config.key_conflicts().len(), 1);
+    let mut app = App::new(MockPueueClient::new(), config);
+    app.refresh_state().await?;
+    app.update_current_task_id();
+    let mut terminal = Terminal::new(TestBackend::new(80, 24))?;
+    let mut press = async |app: &mut App<MockPueueClient>, c, modifiers| -> Result<()> {
+        app.on_key_event(KeyEvent::new(KeyCode::Char(c), modifiers), &mut terminal)
+            .await
+    };
+
+    press(&mut app, 'g', KeyModifiers::NONE).await?;
+    assert!(!dir.path().join("mark").exists());
+    // Whether or not the terminal can be put back in raw mode here, the command ran
+    press(&mut app, 'G', KeyModifiers::SHIFT).await?;
+    assert!(dir.path().join("mark").exists());
+    app.error_modal = None;
+
+    press(&mut app, 'E', KeyModifiers::SHIFT).await?;
+    assert!(matches!(app.app_mode, AppMode::Events(_)));
+    assert!(!dir.path().join("events").exists());
+
+    Ok(())
+}
+
+/// Test that finished runs are appended to the history file once, and survive a reload
+#[test]
+fn test_history_records_finished_runs() -> Result<()> {
+    use crate::history::{self, History};
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("pui").join("history.jsonl");
+    let mut state = MockPueueClient::new().state;
+
+    // Runs that couldn't be written are tried again
+    let mut history = History::load(&path)?;
+    std::fs::write(dir.path().join("pui"), "")?;
+    assert!(history.record(&state, &[]).is_err());
+    std::fs::remove_file(dir.path().join("pui"))?;
+    assert_eq!(history.record(&state, &[])?, 2);
+    // Seeing the same runs again doesn't record them twice
+    assert_eq!(history.record(&state, &[])?, 0);
+    assert_eq!(std::fs::read_to_string(&path)?.lines().count(), 2);
+
+    // A rerun of `false` that took 10 seconds
+    let start = Local.timestamp_opt(1767225700, 0).unwrap();
+    let end = Local.timestamp_opt(1767225710, 0).unwrap();
+    state.tasks.get_mut(&2).unwrap().status = TaskStatus::Done {
+        enqueued_at: start,
+        start,
+        end,
+        result: TaskResult::Success,
+    };
+    let mut history = History::load(&path)?;
+    assert_eq!(history.record(&state, &[])?, 1);
+    assert_eq!(history.record(&state, &[])?, 0);
+
+    let stats = history.command_stats("false").unwrap();
+    assert_eq!((stats.runs, stats.failures), (2, 1));
+    assert_eq!((stats.p50, stats.p95), (0, 10));
+    assert_eq!(stats.failure_rate(), 0.5);
+    assert_eq!(history.recent_durations("false", 20), vec![0, 10]);
+    assert!(history.command_stats("sleep 60").is_none());
+    let commands: Vec<String> = history.stats().into_iter().map(|s| s.command).collect();
+    assert_eq!(commands, vec!["false", "echo 'hello'"]);
+
+    // The same run of another task, or of a task on another daemon, is a run of its own
+    let mut copy = state.tasks[&2].clone();
+    copy.id = 3;
+    state.tasks.insert(3, copy.clone());
+    assert_eq!(history.record(&state, &[])?, 1);
+    let daemons = [
+        crate::daemons::DaemonStatus {
+            name: "local".to_string(),
+            error: None,
+        },
+        crate::daemons::DaemonStatus {
+            name: "build".to_string(),
+            error: None,
+        },
+    ];
+    let mut state = State::default();
+    state.tasks.insert(crate::daemons::ID_STRIDE + 3, copy);
+    assert_eq!(history.record(&state, &daemons)?, 1);
+    assert_eq!(history.record(&state, &daemons)?, 0);
+    let last = std::fs::read_to_string(&path)?
+        .lines()
+        .last()
+        .unwrap()
+        .to_string();
+    assert!(last.contains(r#""task_id":3,"host":"build""#), "{}", last);
+
+    // Records written before runs were told apart by task still load
+    std::fs::write(
+        &path,
+        r#"{"command":"make","path":"/tmp","group":"default","label":null,"start":1,"end":2,"result":"Success","success":true}"#,
+    )?;
+    let history = History::load(&path)?;
+    assert_eq!(history.command_stats("make").unwrap().runs, 1);
+
+    assert_eq!(history::sparkline(&[1, 5, 9]), "▁▄█");
+    assert_eq!(history::sparkline(&[3, 3]), "▄▄");
+    assert_eq!(history::sparkline(&[]), "");
+
+    Ok(())
+}
+
+/// Test that the details popup and the history view show the recorded runs
+#[tokio::test]
+async fn test_history_view() -> Result<()> {
+    use crate::App;
+    use crate::history::History;
+
+    let dir = tempfile::tempdir()?;
+    let mut app = App::new(MockPueueClient::new(), Config::default());
+    app.history = Some(History::load(&dir.path().join("history.jsonl"))?);
+    app.refresh_state().await?;
+
+    let state = app.state.as_ref().unwrap();
+    let details = ui::build_details_text(
+        2,
+        &state.tasks[&2],
+        state,
+        &[],
+        &jiff::Timestamp::from_second(1767225600)?,
+        app.history.as_ref(),
+    );
+    assert!(details.contains("Runs:     1 (100% failed)"), "{}", details);
+
+    let backend = TestBackend::new(80, 24);
+    let mut terminal = Terminal::new(backend)?;
+    app.on_key_event(
+        KeyEvent::new(KeyCode::Char('H'), KeyModifiers::NONE),
+        &mut terminal,
+    )
+    .await?;
+    terminal.draw(|f| app.draw(f))?;
+    let ui = buffer_contents(terminal.backend().buffer());
+    assert!(ui.contains("Run history (2 commands"), "{}", ui);
+    assert!(ui.contains("100%"), "{}", ui);
+
+    Ok(())
+}
+
+/// Test that the log viewer gets the log in a private temporary file, deleted afterwards
+#[test]
+fn test_log_viewer_command() -> Result<()> {
+    let config: Config = toml::from_str(r#"log_viewer = ["bat", "--paging=always"]"#)?;
+    let mut log_state = LogState::new(vec![3]);
+    log_state.push_str(3, "first\n\x1b[31msecond\x1b[0m\n");
+
+    let (cmd, file) = crate::log_viewer_command(&config, &log_state);
+    let file = file?;
+    let path = file.path().to_path_buf();
+    assert_eq!(
+        cmd,
+        vec![
+            "bat".to_string(),
+            "--paging=always".to_string(),
+            path.display().to_string()
+        ]
+    );
+    assert!(
+        path.file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("pui-task-3-")
+    );
+    // Colours are kept for viewers like less -R
+    assert_eq!(
+        std::fs::read_to_string(&path)?,
+        "first\n\x1b[31msecond\x1b[0m\n"
+    );
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(
+            std::fs::metadata(&path)?.permissions().mode() & 0o777,
+            0o600
+        );
+    }
+
+    drop(file);
+    assert!(!path.exists());
+
+    Ok(())
+}
@@ -1,6 +1,12 @@
-use crate::SortField;
-use crate::config::CustomCommand;
-use pueue_lib::state::State;
+use crate::config::{CustomCommand, StatusMatcher};
+use crate::daemons::{self, DaemonStatus};
+use crate::deps::{DepLine, DepRelation};
+use crate::env::{EnvInput, EnvState};
+use crate::events::{EventLog, EventsState};
+use crate::history::{self, History};
+use crate::log::LogState;
+use crate::{CleanOptions, PreviewLayout, SortField};
+use pueue_lib::state::{GroupStatus, State};
 use pueue_lib::task::{Task, TaskResult, TaskStatus};
 use ratatui::{
     Frame,
@@ -8,8 +14,8 @@ use ratatui::{
     style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{
-        Block, Borders, Cell, Clear, Paragraph, Row, Scrollbar, ScrollbarOrientation,
-        ScrollbarState, Table, TableState, Wrap,
+        Block, Borders, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Table, TableState, Wrap,
     },
 };
 use std::collections::{BTreeMap, HashSet};
@@ -33,6 +39,25 @@ pub fn status_display(status: &TaskStatus) -> String {
     }
 }
 
+/// Colour for a task row, based on its display status
+pub fn status_style(status: &str) -> Style {
+    if status == "Running" || status == "Success" {
+        Style::default().fg(Color::Green)
+    } else if status.starts_with("Failed")
+        || status == "Errored"
+        || status == "Killed"
+        || status == "Dependency Failed"
+    {
+        Style::default().fg(Color::Red)
+    } else if status == "Queued" {
+        Style::default().fg(Color::Yellow)
+    } else if status == "Paused" {
+        Style::default().fg(Color::Blue)
+    } else {
+        Style::default().fg(Color::DarkGray)
+    }
+}
+
 pub struct FormattedTask<'a> {
     pub id: String,
     pub status: String,
@@ -59,7 +84,139 @@ impl<'a> FormattedTask<'a> {
     }
 }
 
-pub fn format_task<'a>(id: usize, task: &'a Task, now: &jiff::Timestamp) -> FormattedTask<'a> {
+/// Name of the daemon a task belongs to, when monitoring several
+pub fn host_name(daemons: &[DaemonStatus], id: usize) -> Option<&str> {
+    if daemons.len() > 1 {
+        daemons
+            .get(daemons::daemon_index(id, daemons.len()))
+            .map(|daemon| daemon.name.as_str())
+    } else {
+        None
+    }
+}
+
+/// A task's id as shown to the user: its id on its daemon, followed by the daemon's name
+/// when monitoring several, eg: "3@build"
+pub fn task_label(daemons: &[DaemonStatus], id: usize) -> String {
+    match host_name(daemons, id) {
+        Some(host) => format!("{}@{}", daemons::local_id(id, daemons.len()), host),
+        None => daemons::local_id(id, daemons.len()).to_string(),
+    }
+}
+
+/// The task table as plain text, with columns padded to line up
+pub fn task_list_text(
+    state: &State,
+    task_ids: &[usize],
+    now: &jiff::Timestamp,
+    daemons: &[DaemonStatus],
+) -> String {
+    let multi_daemon = daemons.len() > 1;
+    let mut header = vec!["Id"];
+    if multi_daemon {
+        header.push("Host");
+    }
+    header.extend(["Path suffix", "Command", "End", "Duration", "Status"]);
+    let mut rows: Vec<Vec<String>> = vec![header.into_iter().map(str::to_string).collect()];
+    rows.extend(
+        task_ids
+            .iter()
+            .filter_map(|id| state.tasks.get(id).map(|task| (*id, task)))
+            .map(|(id, task)| {
+                let ft = format_task(id, task, now, daemons);
+                let mut row = vec![ft.id];
+                if let Some(host) = host_name(daemons, id) {
+                    row.push(host.to_string());
+                }
+                row.extend([ft.path, ft.command, ft.end, ft.duration, ft.status]);
+                row
+            }),
+    );
+
+    let mut widths = vec![0; rows[0].len()];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut text = String::new();
+    for row in rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:width$}", cell))
+            .collect();
+        text.push_str(cells.join("  ").trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// A task in the output of `pui list --json`, and the input of hooks.
+/// The table's columns come as shown in the table, with the full command and path added.
+#[derive(serde::Serialize)]
+pub struct TaskJson<'a> {
+    id: usize,
+    /// Daemon of the task, when monitoring several
+    #[serde(skip_serializing_if = "Option::is_none")]
+    host: Option<&'a str>,
+    status: String,
+    command: String,
+    path: String,
+    full_command: &'a str,
+    full_path: String,
+    group: &'a str,
+    label: Option<&'a str>,
+    duration: String,
+    end: String,
+}
+
+/// The tasks as a JSON array, with the fields as pui formats them (eg: a status of "Failed (2)")
+pub fn task_list_json<'a>(
+    state: &'a State,
+    task_ids: &[usize],
+    now: &jiff::Timestamp,
+    daemons: &'a [DaemonStatus],
+) -> serde_json::Result<String> {
+    let tasks: Vec<TaskJson> = task_ids
+        .iter()
+        .filter_map(|id| state.tasks.get(id).map(|task| (*id, task)))
+        .map(|(id, task)| TaskJson::new(id, task, now, daemons))
+        .collect();
+    serde_json::to_string_pretty(&tasks)
+}
+
+impl<'a> TaskJson<'a> {
+    pub fn new(
+        id: usize,
+        task: &'a Task,
+        now: &jiff::Timestamp,
+        daemons: &'a [DaemonStatus],
+    ) -> Self {
+        let ft = format_task(id, task, now, daemons);
+        TaskJson {
+            id: daemons::local_id(id, daemons.len()),
+            host: host_name(daemons, id),
+            status: ft.status,
+            command: ft.command,
+            path: ft.path,
+            full_command: ft.full_command,
+            full_path: ft.full_path,
+            group: ft.group,
+            label: ft.label,
+            duration: ft.duration,
+            end: ft.end,
+        }
+    }
+}
+
+pub fn format_task<'a>(
+    id: usize,
+    task: &'a Task,
+    now: &jiff::Timestamp,
+    daemons: &[DaemonStatus],
+) -> FormattedTask<'a> {
     let (start, end) = task.start_and_end();
     let duration_str = if let Some(start) = start {
         let start_ts = jiff::Timestamp::from_second(start.timestamp()).unwrap();
@@ -103,7 +260,7 @@ pub fn format_task<'a>(id: usize, task: &'a Task, now: &jiff::Timestamp) -> Form
         .to_string();
 
     FormattedTask {
-        id: id.to_string(),
+        id: daemons::local_id(id, daemons.len()).to_string(),
         status: status_display(&task.status),
         command: command_basename,
         path: task
@@ -148,16 +305,41 @@ pub struct UiState<'a> {
     pub task_ids: &'a [usize],
     pub now: jiff::Timestamp,
     pub show_details: bool,
+    pub details_scroll_offset: u16,
     pub filter_text: &'a str,
+    /// Only tasks in this group are shown
+    pub group_filter: Option<&'a str>,
+    /// Only tasks with this status are shown
+    pub status_filter: Option<StatusMatcher>,
+    /// Daemon connected to, if it isn't the default one
+    pub daemon: Option<&'a str>,
+    /// Connection status of each daemon, when monitoring several
+    pub daemons: &'a [DaemonStatus],
     pub input_mode: bool,
     pub sort_mode: bool,
     pub sort_field: SortField,
-    pub log_view: Option<(&'a str, u16)>,
+    pub log_view: Option<&'a LogState>,
     pub connection_error: Option<&'a str>,
     pub error_modal: Option<&'a str>,
     pub selected_task_ids: &'a HashSet<usize>,
     pub help_mode: bool,
     pub help_scroll_offset: u16,
+    /// Index of the highlighted group when picking a group to move tasks to
+    pub group_picker: Option<usize>,
+    pub clean_options: Option<&'a CleanOptions>,
+    pub confirm_reset: bool,
+    pub status_message: Option<&'a str>,
+    /// Dependency graph lines and the index of the highlighted line
+    pub deps_view: Option<(&'a [DepLine], usize)>,
+    pub env_view: Option<&'a EnvState>,
+    /// Run history, for the details popup and the history view
+    pub history: Option<&'a History>,
+    /// Index of the highlighted command in the history view
+    pub history_view: Option<usize>,
+    /// Event log and the index of the highlighted event
+    pub events_view: Option<(&'a EventLog, &'a EventsState)>,
+    /// Where the log preview is shown, and the preview of the highlighted task once loaded
+    pub preview: Option<(PreviewLayout, Option<&'a LogState>)>,
     pub custom_commands: &'a BTreeMap<String, CustomCommand>,
     pub config_path: Option<&'a std::path::Path>,
 }
@@ -176,14 +358,41 @@ pub fn build_help_text(
     help_text.push_str("  r         Run/restart task(s)\n");
     help_text.push_str("  p         Pause task(s)\n");
     help_text.push_str("  x         Kill task(s)\n");
-    help_text.push_str("  Enter     View task logs\n");
+    help_text.push_str("  Enter     View task logs (merged if several)\n");
     help_text.push_str("  Backspace Remove task(s)\n");
+    help_text.push_str("  +/-       Raise/lower priority\n");
+    help_text.push_str("  m         Move task(s) to group, as new task(s)\n");
+    help_text.push_str("  w         Swap two selected tasks\n");
+    help_text.push_str("  C         Clean finished tasks/reset\n");
     help_text.push_str("  Space     Toggle selection\n");
     help_text.push_str("  ?         Show this help\n");
+    help_text.push_str("\nLog Viewer\n");
+    help_text.push_str("  j/k       Scroll down/up\n");
+    help_text.push_str("  d/u       Half page down/up\n");
+    help_text.push_str("  g/G       Top/bottom (follow output)\n");
+    help_text.push_str("  w         Toggle line wrapping\n");
+    help_text.push_str("  h/l, 0/$  Scroll left/right, to start/end\n");
+    help_text.push_str("  #         Toggle line numbers\n");
+    help_text.push_str("  T         Timestamps: off/relative/absolute\n");
+    help_text.push_str("  :N        Go to line N\n");
+    help_text.push_str("  n/N       Next/previous highlighted line\n");
+    help_text.push_str("  E         First error\n");
+    help_text.push_str("  y/Y       Copy the visible lines/the match\n");
+    help_text.push_str("  1-9       Show/hide a task in a merged log\n");
+    help_text.push_str("  s         Save log to a file\n");
+    help_text.push_str("  v         Open log in pager/editor\n");
     help_text.push_str("\nOther\n");
     help_text.push_str("  Ctrl+a    Select all tasks\n");
     help_text.push_str("  d         Show task details\n");
+    help_text.push_str("  D         Show task dependencies\n");
+    help_text.push_str("  e         Task environment (diff if 2 selected)\n");
+    help_text.push_str("  E         Events (task changes, hooks)\n");
+    help_text.push_str("  H         Run history statistics\n");
+    help_text.push_str("  L         Log preview: off/bottom/right\n");
+    help_text.push_str("  y/Y       Copy command/path\n");
     help_text.push_str("  f         Filter tasks\n");
+    help_text.push_str("  Tab/S-Tab Only the next/previous header status\n");
+    help_text.push_str("  >         Only the next group\n");
     help_text.push_str("  s         Sort tasks\n");
     help_text.push_str("  c         Edit config file\n");
     help_text.push_str("  q         Quit\n");
@@ -210,8 +419,13 @@ pub(crate) fn help_modal_line_count(
     content_height: u16,
 ) -> u16 {
     let help_text = build_help_text(custom_commands, config_path);
+    modal_line_count(&help_text, content_width, content_height)
+}
+
+/// Number of wrapped lines `text` takes up in a scrollable modal
+pub(crate) fn modal_line_count(text: &str, content_width: u16, content_height: u16) -> u16 {
     let width = content_width.max(1);
-    let paragraph = Paragraph::new(help_text).wrap(Wrap { trim: false });
+    let paragraph = Paragraph::new(text).wrap(Wrap { trim: false });
     let mut line_count = paragraph.line_count(width) as u16;
 
     if line_count > content_height && width > 1 {
@@ -222,25 +436,588 @@ pub(crate) fn help_modal_line_count(
     line_count
 }
 
-pub fn draw(f: &mut Frame, ui_state: &mut UiState) {
-    if let Some((logs, scroll_offset)) = ui_state.log_view {
-        let size = f.area();
-        let block = Block::default()
-            .borders(Borders::ALL)
-            .title(" Task Log (Esc to close) ");
+/// Render a vertical scrollbar inside the border of a scrollable modal
+fn render_modal_scrollbar(
+    f: &mut Frame,
+    area: Rect,
+    line_count: u16,
+    content_height: u16,
+    scroll_offset: u16,
+) {
+    if line_count <= content_height {
+        return;
+    }
+    let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .begin_symbol(Some("↑"))
+        .end_symbol(Some("↓"));
+    let line_count = line_count as usize;
+    let max_offset = line_count.saturating_sub(content_height as usize);
+    // Map scroll offset to full content range so the thumb reaches both ends.
+    // This is needed because the scroll offset is a top-of-viewport index,
+    // while the scrollbar thumb position expects a position within the full content range.
+    // Passing the raw offset means the thumb never reaches the bottom.
+    // So we map offset from [0..max_offset] to [0..line_count-1]
+    // The task table doesn't need this because it uses the selected row index.
+    let position = if max_offset == 0 || line_count <= 1 {
+        0
+    } else {
+        let scaled =
+            (scroll_offset as usize).saturating_mul(line_count.saturating_sub(1)) / max_offset;
+        scaled.min(line_count.saturating_sub(1))
+    };
+    let mut scrollbar_state = ScrollbarState::new(line_count)
+        .viewport_content_length(content_height as usize)
+        .position(position);
+    // rnender scrollbar within the modal border
+    let scrollbar_area = area.inner(Margin {
+        vertical: 1,
+        horizontal: 1,
+    });
+    f.render_stateful_widget(scrollbar, scrollbar_area, &mut scrollbar_state);
+}
 
-        // default tab stop width in terminals is typically 8 characters
-        let logs = logs.replace('\t', "        ");
-        let p = Paragraph::new(logs)
-            .block(block)
-            .wrap(Wrap { trim: false })
-            .scroll((scroll_offset, 0)); // (y, x)
+/// Statuses counted in the summary header, in the order Tab goes through them
+pub const SUMMARY_STATUSES: [StatusMatcher; 6] = [
+    StatusMatcher::Running,
+    StatusMatcher::Queued,
+    StatusMatcher::Paused,
+    StatusMatcher::Stashed,
+    StatusMatcher::Failure,
+    StatusMatcher::Success,
+];
+
+fn summary_label(status: StatusMatcher) -> &'static str {
+    match status {
+        StatusMatcher::Running => "Running",
+        StatusMatcher::Queued => "Queued",
+        StatusMatcher::Paused => "Paused",
+        StatusMatcher::Stashed => "Stashed",
+        StatusMatcher::Done => "Done",
+        StatusMatcher::Success => "Succeeded",
+        StatusMatcher::Failure => "Failed",
+    }
+}
+
+/// Task counts per status, with the count the table is filtered by highlighted
+pub fn status_summary(state: &State, status_filter: Option<StatusMatcher>) -> Line<'static> {
+    let mut spans = Vec::new();
+    for (i, status) in SUMMARY_STATUSES.into_iter().enumerate() {
+        let count = state
+            .tasks
+            .values()
+            .filter(|task| status.matches(&task.status))
+            .count();
+        if i > 0 {
+            spans.push(Span::raw("  "));
+        }
+        let mut style = match status {
+            StatusMatcher::Running | StatusMatcher::Success => Style::default().fg(Color::Green),
+            StatusMatcher::Failure => Style::default().fg(Color::Red),
+            StatusMatcher::Paused => Style::default().fg(Color::Yellow),
+            _ => Style::default(),
+        };
+        if count == 0 {
+            style = Style::default().fg(Color::DarkGray);
+        }
+        if status_filter == Some(status) {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+        spans.push(Span::styled(
+            format!("{} {}", summary_label(status), count),
+            style,
+        ));
+    }
+    Line::from(spans)
+}
+
+/// Total runtime of the running tasks, eg: "Runtime 1h 2m 3s", None if nothing is running
+pub fn runtime_summary(state: &State, now: &jiff::Timestamp) -> Option<String> {
+    let mut running = false;
+    let mut runtime = 0;
+    for task in state.tasks.values() {
+        if let TaskStatus::Running { start, .. } = &task.status {
+            running = true;
+            runtime += (now.as_second() - start.timestamp()).max(0);
+        }
+    }
+    running.then(|| format!("Runtime {}", format_exact_duration(runtime)))
+}
+
+/// Running tasks against the parallel limit of each group, and whether the daemon is paused
+pub fn groups_summary(state: &State) -> Line<'static> {
+    let mut spans = Vec::new();
+    let all_paused = !state.groups.is_empty()
+        && state
+            .groups
+            .values()
+            .all(|group| group.status == GroupStatus::Paused);
+    if all_paused {
+        spans.push(Span::styled(
+            " Daemon paused ",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+    }
+    for (name, group) in &state.groups {
+        let running = state
+            .tasks
+            .values()
+            .filter(|task| task.group == *name && matches!(task.status, TaskStatus::Running { .. }))
+            .count();
+        let limit = match group.parallel_tasks {
+            0 => "∞".to_string(),
+            n => n.to_string(),
+        };
+        let mut text = format!(" {} {}/{} ", name, running, limit);
+        let mut style = Style::default();
+        if group.status == GroupStatus::Paused && !all_paused {
+            text = format!(" {} {}/{} paused ", name, running, limit);
+            style = style.fg(Color::Yellow);
+        }
+        spans.push(Span::styled(text, style));
+    }
+    Line::from(spans)
+}
+
+/// Format a size in bytes, eg: "1.5 KiB"
+pub fn format_size(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Format a duration in seconds, eg: "1h 2m 3s"
+fn format_exact_duration(secs: i64) -> String {
+    let (h, m, s) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+    if h > 0 {
+        format!("{}h {}m {}s", h, m, s)
+    } else if m > 0 {
+        format!("{}m {}s", m, s)
+    } else {
+        format!("{}s", s)
+    }
+}
+
+/// Format a timestamp in seconds as local time, eg: "2026-01-01 12:00:00"
+pub fn format_local_time(secs: i64) -> String {
+    jiff::Timestamp::from_second(secs)
+        .unwrap_or_default()
+        .to_zoned(jiff::tz::TimeZone::system())
+        .strftime("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
 
-        f.render_widget(p, size);
+fn format_local_and_relative(secs: i64, now: &jiff::Timestamp) -> String {
+    let local = format_local_time(secs);
+    let secs = now.as_second() - secs;
+    let relative = if secs >= 0 {
+        format!("{} ago", format_exact_duration(secs))
+    } else {
+        format!("in {}", format_exact_duration(-secs))
+    };
+    format!("{} ({})", local, relative)
+}
+
+/// Explain why a task that hasn't finished is in its current status
+fn status_note(task: &Task, state: &State) -> Option<String> {
+    match &task.status {
+        TaskStatus::Locked { .. } => Some("being edited".to_string()),
+        TaskStatus::Stashed { enqueue_at: None } => Some("won't run until enqueued".to_string()),
+        TaskStatus::Stashed {
+            enqueue_at: Some(_),
+        } => Some("will be enqueued at the scheduled time".to_string()),
+        TaskStatus::Queued { .. } => {
+            let waiting_on = task
+                .dependencies
+                .iter()
+                .filter(|id| state.tasks.get(id).is_some_and(|t| !t.is_done()))
+                .count();
+            if waiting_on > 0 {
+                Some(format!(
+                    "waiting for {} dependency(s) to finish",
+                    waiting_on
+                ))
+            } else {
+                Some("waiting for a free slot in its group".to_string())
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Explain what a finished task's result means
+fn result_description(result: &TaskResult) -> String {
+    match result {
+        TaskResult::Success => "Exited with code 0".to_string(),
+        // Shells report death by signal N as exit code 128 + N
+        TaskResult::Failed(code) if *code > 128 && *code < 160 => format!(
+            "Exited with code {} (likely terminated by signal {})",
+            code,
+            code - 128
+        ),
+        TaskResult::Failed(code) => format!("Exited with non-zero code {}", code),
+        TaskResult::FailedToSpawn(reason) => format!("Failed to spawn: {}", reason),
+        TaskResult::Killed => "Killed by the user or on daemon shutdown".to_string(),
+        TaskResult::Errored => "IO error while running, check the daemon logs".to_string(),
+        TaskResult::DependencyFailed => "Not run because a dependency failed".to_string(),
+    }
+}
+
+/// Number of runs in the sparklines of recent durations
+const RECENT_RUNS: usize = 20;
+
+/// Build the text of the details popup for a task
+pub fn build_details_text(
+    id: usize,
+    task: &Task,
+    state: &State,
+    daemons: &[DaemonStatus],
+    now: &jiff::Timestamp,
+    history: Option<&History>,
+) -> String {
+    let ft = format_task(id, task, now, daemons);
+    let mut details = format!("ID: {}\nStatus: {}", task_label(daemons, id), ft.status);
+    if let Some(note) = status_note(task, state) {
+        details.push_str(&format!(" ({})", note));
+    }
+    details.push('\n');
+    if let TaskStatus::Done { result, .. } = &task.status {
+        details.push_str(&format!("Result: {}\n", result_description(result)));
+    }
+    details.push_str(&format!(
+        "Group: {}\nPriority: {}\n",
+        ft.group, task.priority
+    ));
+    if let Some(label) = ft.label {
+        details.push_str(&format!("Label: {}\n", label));
+    }
+
+    details.push_str("\nTimeline\n");
+    details.push_str(&format!(
+        "  Created:  {}\n",
+        format_local_and_relative(task.created_at.timestamp(), now)
+    ));
+    let enqueued = match &task.status {
+        TaskStatus::Queued { enqueued_at }
+        | TaskStatus::Running { enqueued_at, .. }
+        | TaskStatus::Paused { enqueued_at, .. }
+        | TaskStatus::Done { enqueued_at, .. } => Some(enqueued_at),
+        TaskStatus::Stashed { enqueue_at } => enqueue_at.as_ref(),
+        TaskStatus::Locked { .. } => None,
+    };
+    if let Some(enqueued) = enqueued {
+        details.push_str(&format!(
+            "  Enqueued: {}\n",
+            format_local_and_relative(enqueued.timestamp(), now)
+        ));
+    }
+    let (start, end) = task.start_and_end();
+    if let Some(start) = &start {
+        details.push_str(&format!(
+            "  Started:  {}\n",
+            format_local_and_relative(start.timestamp(), now)
+        ));
+    }
+    if let Some(end) = &end {
+        details.push_str(&format!(
+            "  Ended:    {}\n",
+            format_local_and_relative(end.timestamp(), now)
+        ));
+    }
+    if let Some(start) = &start {
+        let end_secs = end.map_or(now.as_second(), |e| e.timestamp());
+        let secs = end_secs - start.timestamp();
+        details.push_str(&format!(
+            "  Duration: {} ({}s){}\n",
+            format_exact_duration(secs),
+            secs,
+            if end.is_none() { " so far" } else { "" }
+        ));
+    }
+
+    if let Some(history) = history
+        && let Some(stats) = history.command_stats(&task.command)
+    {
+        details.push_str("\nHistory\n");
+        details.push_str(&format!(
+            "  Runs:     {} ({:.0}% failed)\n",
+            stats.runs,
+            stats.failure_rate() * 100.0
+        ));
+        details.push_str(&format!(
+            "  Duration: {} median, {} p95\n",
+            format_exact_duration(stats.p50),
+            format_exact_duration(stats.p95)
+        ));
+        let recent = history.recent_durations(&task.command, RECENT_RUNS);
+        details.push_str(&format!("  Recent:   {}\n", history::sparkline(&recent)));
+    }
+
+    if !task.dependencies.is_empty() {
+        details.push_str("\nDependencies\n");
+        for dependency in &task.dependencies {
+            match state.tasks.get(dependency) {
+                Some(dep) => details.push_str(&format!(
+                    "  #{} {}  {}\n",
+                    task_label(daemons, *dependency),
+                    status_display(&dep.status),
+                    dep.command
+                )),
+                None => details.push_str(&format!(
+                    "  #{} (removed)\n",
+                    task_label(daemons, *dependency)
+                )),
+            }
+        }
+    }
+
+    details.push_str(&format!("\nFull Path: {}\n", ft.full_path));
+    if task.original_command != task.command {
+        details.push_str(&format!(
+            "Original Command: {}\nEffective Command: {}\n",
+            task.original_command, task.command
+        ));
+    } else {
+        details.push_str(&format!("Full Command: {}\n", ft.full_command));
+    }
+
+    details
+}
+
+/// Draw the run statistics of each command in the history
+fn draw_history_view(f: &mut Frame, history: Option<&History>, selected: usize) {
+    let stats = history.map(History::stats).unwrap_or_default();
+    let area = centered_rect(90, 80, f.area());
+    f.render_widget(Clear, area);
+    let block = Block::default().borders(Borders::ALL).title(format!(
+        " Run history ({} commands, Esc to close) ",
+        stats.len()
+    ));
+
+    if stats.is_empty() {
+        let text = match history {
+            Some(_) => "No finished runs recorded yet",
+            None => "The run history couldn't be loaded",
+        };
+        let paragraph =
+            Paragraph::new(Span::styled(text, Style::default().fg(Color::DarkGray))).block(block);
+        f.render_widget(paragraph, area);
         return;
     }
 
-    let chunks = Layout::default()
+    let rows: Vec<Row> = stats
+        .iter()
+        .map(|stats| {
+            let failed_style = if stats.failures > 0 {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default()
+            };
+            let recent = history
+                .map(|h| h.recent_durations(&stats.command, RECENT_RUNS))
+                .unwrap_or_default();
+            Row::new(vec![
+                Cell::from(stats.command.clone()),
+                Cell::from(stats.runs.to_string()),
+                Cell::from(format!("{:.0}%", stats.failure_rate() * 100.0)).style(failed_style),
+                Cell::from(format_exact_duration(stats.p50)),
+                Cell::from(format_exact_duration(stats.p95)),
+                Cell::from(history::sparkline(&recent)),
+            ])
+        })
+        .collect();
+    let header = Row::new(vec!["Command", "Runs", "Failed", "p50", "p95", "Recent"]).style(
+        Style::default()
+            .add_modifier(Modifier::BOLD)
+            .fg(Color::Cyan),
+    );
+    let widths = [
+        Constraint::Min(20),
+        Constraint::Length(5),
+        Constraint::Length(6),
+        Constraint::Length(10),
+        Constraint::Length(10),
+        Constraint::Length(RECENT_RUNS as u16),
+    ];
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(block)
+        .row_highlight_style(
+            Style::default()
+                .add_modifier(Modifier::BOLD)
+                .bg(Color::Rgb(50, 50, 50)),
+        )
+        .highlight_symbol(">> ");
+    let mut table_state = TableState::default().with_selected(Some(selected));
+    f.render_stateful_widget(table, area, &mut table_state);
+}
+
+/// Draw the event panel, newest events first
+fn draw_events_view(
+    f: &mut Frame,
+    events: &EventLog,
+    events_state: &EventsState,
+    daemons: &[DaemonStatus],
+) {
+    let items: Vec<ListItem> = events
+        .filtered(events_state.task_filter)
+        .map(|event| {
+            let time = event
+                .at
+                .to_zoned(jiff::tz::TimeZone::system())
+                .strftime("%H:%M:%S")
+                .to_string();
+            let task = event
+                .task_id
+                .map(|id| format!("#{:<4}", task_label(daemons, id)))
+                .unwrap_or_else(|| " ".repeat(5));
+            let style = if event.error {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default()
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{} ", time), Style::default().fg(Color::DarkGray)),
+                Span::raw(format!("{} ", task)),
+                Span::styled(event.message.clone(), style),
+            ]))
+        })
+        .collect();
+
+    let area = centered_rect(80, 80, f.area());
+    f.render_widget(Clear, area);
+
+    let title = match events_state.task_filter {
+        Some(id) => format!(
+            " Events of task {} ({}) ",
+            task_label(daemons, id),
+            items.len()
+        ),
+        None => format!(" Events ({}) ", items.len()),
+    };
+    let selected = (!items.is_empty()).then_some(events_state.selected);
+    let list = if items.is_empty() {
+        List::new(vec![ListItem::new(Span::styled(
+            "No events yet",
+            Style::default().fg(Color::DarkGray),
+        ))])
+    } else {
+        List::new(items)
+    };
+    let list = list
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(
+            Style::default()
+                .add_modifier(Modifier::BOLD)
+                .bg(Color::Rgb(50, 50, 50)),
+        )
+        .highlight_symbol(">> ");
+    let mut list_state = ListState::default().with_selected(selected);
+    f.render_stateful_widget(list, area, &mut list_state);
+}
+
+/// Draw the environment viewer popup (single task or diff of two tasks)
+fn draw_env_view(f: &mut Frame, env_state: &EnvState, daemons: &[DaemonStatus]) {
+    let rows = env_state.rows();
+    let unset = || Cell::from("(unset)").style(Style::default().fg(Color::DarkGray));
+
+    let table_rows: Vec<Row> = rows
+        .iter()
+        .map(|row| {
+            let value = row.value.map_or_else(unset, Cell::from);
+            if env_state.compare.is_some() {
+                let other = row.other.map_or_else(unset, Cell::from);
+                Row::new(vec![Cell::from(" "), Cell::from(row.key), value, other])
+            } else if row.modified {
+                Row::new(vec![Cell::from("*"), Cell::from(row.key), value])
+                    .style(Style::default().fg(Color::Yellow))
+            } else {
+                Row::new(vec![Cell::from(" "), Cell::from(row.key), value])
+            }
+        })
+        .collect();
+
+    let header_style = Style::default()
+        .add_modifier(Modifier::BOLD)
+        .fg(Color::Cyan);
+    let (header, widths, title) = if let Some((other_id, _)) = &env_state.compare {
+        (
+            Row::new(vec![
+                " ".to_string(),
+                "Variable".to_string(),
+                format!("Task {}", task_label(daemons, env_state.task_id)),
+                format!("Task {}", task_label(daemons, *other_id)),
+            ]),
+            vec![
+                Constraint::Length(1),
+                Constraint::Percentage(30),
+                Constraint::Percentage(35),
+                Constraint::Percentage(35),
+            ],
+            format!(
+                " Environment diff: task {} vs task {} ({} differ, Esc to close) ",
+                task_label(daemons, env_state.task_id),
+                task_label(daemons, *other_id),
+                rows.len()
+            ),
+        )
+    } else {
+        (
+            Row::new(vec![
+                " ".to_string(),
+                "Variable".to_string(),
+                "Value".to_string(),
+            ]),
+            vec![
+                Constraint::Length(1),
+                Constraint::Percentage(35),
+                Constraint::Percentage(65),
+            ],
+            format!(
+                " Environment of task {} ({} variables, Esc to close) ",
+                task_label(daemons, env_state.task_id),
+                rows.len()
+            ),
+        )
+    };
+
+    let area = centered_rect(90, 80, f.area());
+    f.render_widget(Clear, area);
+
+    let table = Table::new(table_rows, widths)
+        .header(header.style(header_style))
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .row_highlight_style(
+            Style::default()
+                .add_modifier(Modifier::BOLD)
+                .bg(Color::Rgb(50, 50, 50)),
+        );
+    let mut table_state = TableState::default().with_selected(Some(env_state.selected));
+    f.render_stateful_widget(table, area, &mut table_state);
+
+    // Borders and the header row
+    let visible_rows = area.height.saturating_sub(3) as usize;
+    if rows.len() > visible_rows {
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(Some("↑"))
+            .end_symbol(Some("↓"));
+        let mut scrollbar_state = ScrollbarState::new(rows.len())
+            .viewport_content_length(visible_rows)
+            .position(env_state.selected);
+        f.render_stateful_widget(scrollbar, area, &mut scrollbar_state);
+    }
+}
+
+/// Split the screen into the title, table and footer areas
+fn main_chunks(area: Rect) -> std::rc::Rc<[Rect]> {
+    Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
         .constraints(
@@ -251,19 +1028,128 @@ pub fn draw(f: &mut Frame, ui_state: &mut UiState) {
             ]
             .as_ref(),
         )
-        .split(f.area());
+        .split(area)
+}
+
+/// Split the table area between the table and the log preview
+fn split_table_area(table_area: Rect, layout: PreviewLayout) -> (Rect, Option<Rect>) {
+    let direction = match layout {
+        PreviewLayout::Off => return (table_area, None),
+        PreviewLayout::Bottom => Direction::Vertical,
+        PreviewLayout::Right => Direction::Horizontal,
+    };
+    let areas = Layout::default()
+        .direction(direction)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(table_area);
+    (areas[0], Some(areas[1]))
+}
+
+/// Area of the log preview pane, including its borders
+pub fn preview_area(area: Rect, layout: PreviewLayout) -> Option<Rect> {
+    split_table_area(main_chunks(area)[1], layout).1
+}
+
+/// Render the visible part of a log in a block, with the line number gutter if enabled
+fn render_log(f: &mut Frame, area: Rect, block: Block, log_state: &LogState) {
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let mut text_area = inner;
+    if log_state.gutter_width() > 0 {
+        let gutter_width = log_state.gutter_width().min(inner.width);
+        text_area.x += gutter_width;
+        text_area.width -= gutter_width;
+        let gutter = Paragraph::new(log_state.gutter_lines(inner.height, text_area.width));
+        f.render_widget(
+            gutter,
+            Rect {
+                width: gutter_width,
+                ..inner
+            },
+        );
+    }
+
+    // Only the lines in view are rendered, so drawing doesn't slow down with large logs
+    let (lines, skip_rows) = log_state.visible_lines(text_area.height);
+    let p = if log_state.wrap {
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .scroll((skip_rows, 0)) // (y, x)
+    } else {
+        Paragraph::new(lines).scroll((0, log_state.left))
+    };
+
+    f.render_widget(p, text_area);
+}
+
+pub fn draw(f: &mut Frame, ui_state: &mut UiState) {
+    if let Some(log_state) = ui_state.log_view {
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" {} (Esc to close) ", log_state.summary()));
+        if let Some(prompt) = &log_state.save_prompt {
+            block = block.title_bottom(Line::from(vec![
+                Span::styled(" Save to: ", Style::default().fg(Color::Yellow)),
+                Span::raw(format!("{}_ ", prompt.path)),
+                Span::raw(format!(
+                    "| ^S strip ANSI: {} | ^T header: {} | Enter: save | Esc: cancel ",
+                    if prompt.strip_ansi { "yes" } else { "no" },
+                    if prompt.header { "yes" } else { "no" },
+                )),
+            ]));
+        } else if let Some(input) = &log_state.jump_input {
+            block = block.title_bottom(Line::from(vec![
+                Span::styled(" Go to line: ", Style::default().fg(Color::Yellow)),
+                Span::raw(format!("{}_ ", input)),
+            ]));
+        } else if let Some(message) = &log_state.message {
+            block = block.title_bottom(format!(" {} ", message));
+        }
+
+        render_log(f, f.area(), block, log_state);
+        return;
+    }
 
-    let title_block = Block::default()
+    let chunks = main_chunks(f.area());
+
+    let mut title_block = Block::default()
         .borders(Borders::ALL)
-        .title(" Pui - Pueue TUI ");
-    let title = Paragraph::new(
-        "r: Run | p: Pause | x: Kill | Enter: Logs | Backspace: Remove | Space: Select | ?: Help",
-    )
-    .block(title_block);
+        .title(match ui_state.daemon {
+            Some(daemon) => format!(" Pui - Pueue TUI ({}) ", daemon),
+            None => " Pui - Pueue TUI ".to_string(),
+        })
+        .title_bottom(Line::from(" ?: Help ").right_aligned());
+    let summary = match &ui_state.state {
+        Some(s) => {
+            title_block = title_block.title(groups_summary(s).right_aligned());
+            if let Some(runtime) = runtime_summary(s, &ui_state.now) {
+                title_block = title_block.title_bottom(format!(" {} ", runtime));
+            }
+            status_summary(s, ui_state.status_filter)
+        }
+        None => Line::default(),
+    };
+    let title = Paragraph::new(summary).block(title_block);
     f.render_widget(title, chunks[0]);
 
-    // Use full width for the table (chunks[1])
-    let table_area = chunks[1];
+    // The table takes the whole of chunks[1], unless it's shared with the log preview
+    let (table_area, preview_area) = match ui_state.preview {
+        Some((layout, _)) => split_table_area(chunks[1], layout),
+        None => (chunks[1], None),
+    };
+    if let Some(area) = preview_area {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::DarkGray));
+        match ui_state.preview.and_then(|(_, preview)| preview) {
+            Some(log_state) => {
+                let block = block.title(format!(" Preview: {} ", log_state.summary()));
+                render_log(f, area, block, log_state);
+            }
+            None => f.render_widget(block.title(" Preview "), area),
+        }
+    }
 
     if let Some(s) = &ui_state.state {
         let rows: Vec<Row> = ui_state
@@ -271,21 +1157,8 @@ pub fn draw(f: &mut Frame, ui_state: &mut UiState) {
             .iter()
             .filter_map(|id| s.tasks.get(id).map(|task| (*id, task)))
             .map(|(id, task)| {
-                let ft = format_task(id, task, &ui_state.now);
-                let style = if ft.status == "Running" || ft.status == "Success" {
-                    Style::default().fg(Color::Green)
-                } else if ft.status.starts_with("Failed")
-                    || ft.status == "Errored"
-                    || ft.status == "Killed"
-                {
-                    Style::default().fg(Color::Red)
-                } else if ft.status == "Queued" {
-                    Style::default().fg(Color::Yellow)
-                } else if ft.status == "Paused" {
-                    Style::default().fg(Color::Blue)
-                } else {
-                    Style::default().fg(Color::DarkGray)
-                };
+                let ft = format_task(id, task, &ui_state.now, ui_state.daemons);
+                let style = status_style(&ft.status);
 
                 // Selection indicator: * for selected, empty for unselected
                 let indicator = if ui_state.selected_task_ids.contains(&id) {
@@ -294,47 +1167,64 @@ pub fn draw(f: &mut Frame, ui_state: &mut UiState) {
                     " "
                 };
 
-                Row::new(vec![
-                    Cell::from(indicator),
-                    Cell::from(ft.id),
+                let mut cells = vec![Cell::from(indicator), Cell::from(ft.id)];
+                if let Some(host) = host_name(ui_state.daemons, id) {
+                    cells.push(Cell::from(host.to_string()));
+                }
+                cells.extend([
                     Cell::from(ft.path),
                     Cell::from(ft.command),
                     Cell::from(ft.end),
                     Cell::from(ft.duration),
                     Cell::from(ft.status),
-                ])
-                .style(style)
+                ]);
+                Row::new(cells).style(style)
             })
             .collect();
 
-        let header = Row::new(vec![
-            " ",
-            "Id",
-            "Path suffix",
-            "Command",
-            "End",
-            "Duration",
-            "Status",
-        ])
-        .style(
+        // The host column is only shown when monitoring several daemons
+        let multi_daemon = ui_state.daemons.len() > 1;
+        let mut header_cells = vec![" ", "Id"];
+        if multi_daemon {
+            header_cells.push("Host");
+        }
+        header_cells.extend(["Path suffix", "Command", "End", "Duration", "Status"]);
+        let header = Row::new(header_cells).style(
             Style::default()
                 .add_modifier(Modifier::BOLD)
                 .fg(Color::Cyan),
         );
 
-        let widths = [
-            Constraint::Length(1),      // Select indicator
-            Constraint::Length(4),      // Id
+        let mut widths = vec![
+            Constraint::Length(1), // Select indicator
+            Constraint::Length(4), // Id
+        ];
+        if multi_daemon {
+            widths.push(Constraint::Length(12)); // Host
+        }
+        widths.extend([
             Constraint::Percentage(50), // Path
             Constraint::Percentage(50), // Command
             Constraint::Length(10),     // End
             Constraint::Length(10),     // Duration
             Constraint::Length(12),     // Status
-        ];
+        ]);
 
+        let mut filters = Vec::new();
+        if let Some(status) = ui_state.status_filter {
+            filters.push(summary_label(status).to_lowercase());
+        }
+        if let Some(group) = ui_state.group_filter {
+            filters.push(format!("group: {}", group));
+        }
+        let title = if filters.is_empty() {
+            " Tasks ".to_string()
+        } else {
+            format!(" Tasks ({}) ", filters.join(", "))
+        };
         let task_table = Table::new(rows, widths)
             .header(header)
-            .block(Block::default().borders(Borders::ALL).title(" Tasks "))
+            .block(Block::default().borders(Borders::ALL).title(title))
             .row_highlight_style(
                 Style::default()
                     .add_modifier(Modifier::BOLD)
@@ -366,20 +1256,14 @@ pub fn draw(f: &mut Frame, ui_state: &mut UiState) {
 
             let details_text = if let Some(id) = selected_id {
                 if let Some(task) = s.tasks.get(id) {
-                    let ft = format_task(*id, task, &ui_state.now);
-
-                    let mut details = format!(
-                        "ID: {}\nStatus: {}\nCommand: {}\nPath: {}\nEnd: {}\nDuration: {}\nGroup: {}\n",
-                        ft.id, ft.status, ft.command, ft.path, ft.end, ft.duration, ft.group
-                    );
-                    if let Some(label) = ft.label {
-                        details.push_str(&format!("Label: {}\n", label));
-                    }
-                    details.push_str(&format!(
-                        "\nFull Command: {}\nFull Path: {}\n",
-                        ft.full_command, ft.full_path
-                    ));
-                    details
+                    build_details_text(
+                        *id,
+                        task,
+                        s,
+                        ui_state.daemons,
+                        &ui_state.now,
+                        ui_state.history,
+                    )
                 } else {
                     "Task not found".to_string()
                 }
@@ -390,14 +1274,121 @@ pub fn draw(f: &mut Frame, ui_state: &mut UiState) {
             let area = centered_rect(60, 60, f.area());
             f.render_widget(Clear, area); // Clear the background
 
+            let content_width = area.width.saturating_sub(2).max(1);
+            let content_height = area.height.saturating_sub(2);
+            let line_count = modal_line_count(&details_text, content_width, content_height);
+
             let details_block = Paragraph::new(details_text)
                 .block(
                     Block::default()
                         .borders(Borders::ALL)
                         .title(" Details (Esc to close) "),
                 )
-                .wrap(Wrap { trim: false });
+                .wrap(Wrap { trim: false })
+                .scroll((ui_state.details_scroll_offset, 0));
             f.render_widget(details_block, area);
+            render_modal_scrollbar(
+                f,
+                area,
+                line_count,
+                content_height,
+                ui_state.details_scroll_offset,
+            );
+        }
+
+        // Group picker popup
+        if let Some(selected) = ui_state.group_picker {
+            let items: Vec<ListItem> = s
+                .groups
+                .iter()
+                .map(|(name, group)| {
+                    ListItem::new(format!("{} ({} parallel)", name, group.parallel_tasks))
+                })
+                .collect();
+
+            let area = centered_rect(40, 40, f.area());
+            f.render_widget(Clear, area);
+
+            let list = List::new(items)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(" Move to group (Enter to move, Esc to cancel) "),
+                )
+                .highlight_style(
+                    Style::default()
+                        .add_modifier(Modifier::BOLD)
+                        .bg(Color::Rgb(50, 50, 50)),
+                )
+                .highlight_symbol(">> ");
+            let mut list_state = ListState::default().with_selected(Some(selected));
+            f.render_stateful_widget(list, area, &mut list_state);
+        }
+
+        // Dependency graph popup
+        if let Some((lines, selected)) = ui_state.deps_view {
+            let items: Vec<ListItem> = lines
+                .iter()
+                .map(|line| {
+                    let marker = match line.relation {
+                        DepRelation::Root => "",
+                        DepRelation::DependsOn => "← ",
+                        DepRelation::RequiredBy => "→ ",
+                    };
+                    let indent = "    ".repeat(line.depth.saturating_sub(1));
+                    let prefix = if line.depth > 0 { "  " } else { "" };
+                    let mut spans = vec![Span::raw(format!("{prefix}{indent}{marker}"))];
+                    if let Some(task) = s.tasks.get(&line.task_id) {
+                        let ft = format_task(line.task_id, task, &ui_state.now, ui_state.daemons);
+                        let style = status_style(&ft.status);
+                        spans.push(Span::styled(
+                            format!("#{} ", task_label(ui_state.daemons, line.task_id)),
+                            style,
+                        ));
+                        spans.push(Span::styled(ft.status, style.add_modifier(Modifier::BOLD)));
+                        spans.push(Span::raw(format!("  {}", ft.full_command)));
+                        if line.repeated {
+                            spans.push(Span::styled(
+                                " (see above)",
+                                Style::default().fg(Color::DarkGray),
+                            ));
+                        }
+                    } else {
+                        spans.push(Span::styled(
+                            format!("#{} (removed)", task_label(ui_state.daemons, line.task_id)),
+                            Style::default().fg(Color::DarkGray),
+                        ));
+                    }
+                    ListItem::new(Line::from(spans))
+                })
+                .collect();
+
+            let area = centered_rect(80, 80, f.area());
+            f.render_widget(Clear, area);
+
+            let title = " Dependencies: ← depends on, → required by (Esc to close) ";
+            let list = List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(title))
+                .highlight_style(
+                    Style::default()
+                        .add_modifier(Modifier::BOLD)
+                        .bg(Color::Rgb(50, 50, 50)),
+                )
+                .highlight_symbol(">> ");
+            let mut list_state = ListState::default().with_selected(Some(selected));
+            f.render_stateful_widget(list, area, &mut list_state);
+        }
+
+        if let Some(env_state) = ui_state.env_view {
+            draw_env_view(f, env_state, ui_state.daemons);
+        }
+
+        if let Some(selected) = ui_state.history_view {
+            draw_history_view(f, ui_state.history, selected);
+        }
+
+        if let Some((events, events_state)) = ui_state.events_view {
+            draw_events_view(f, events, events_state, ui_state.daemons);
         }
     } else {
         let loading = Paragraph::new("Loading state from Pueue...")
@@ -407,6 +1398,46 @@ pub fn draw(f: &mut Frame, ui_state: &mut UiState) {
 
     let footer_content: Line = if let Some(error) = ui_state.connection_error {
         Line::from(error).style(Style::default().fg(Color::Red))
+    } else if let Some(env_state) = ui_state.env_view {
+        match &env_state.input {
+            Some((EnvInput::Filter, buffer)) => Line::from(format!(
+                "Filter: {}_ (Enter to apply, Esc to clear)",
+                buffer
+            )),
+            Some((EnvInput::Edit(name), buffer)) => Line::from(format!(
+                "{}={}_ (Enter to save, Esc to cancel)",
+                name, buffer
+            )),
+            Some((EnvInput::Add, buffer)) => Line::from(format!(
+                "Add NAME=value: {}_ (Enter to add, Esc to cancel)",
+                buffer
+            )),
+            None if env_state.compare.is_some() => Line::from("/: Filter | j/k: Move | Esc: Close"),
+            None => {
+                let modified = if env_state.is_modified() {
+                    " (modified)"
+                } else {
+                    ""
+                };
+                Line::from(format!(
+                    "/: Filter | e: Edit | a: Add | x: Unset | u: Undo | r: Restart with env{} | Esc: Close",
+                    modified
+                ))
+            }
+        }
+    } else if ui_state.history_view.is_some() {
+        Line::from("j/k: Move | Esc: Close")
+    } else if ui_state.events_view.is_some() {
+        Line::from("j/k: Move | t: Only this task/all tasks | Enter: Jump to task | Esc: Close")
+    } else if ui_state.deps_view.is_some() {
+        Line::from("j/k: Move | l: Re-root on task | h: Back | Enter: Jump to task | Esc: Close")
+    } else if let Some(options) = ui_state.clean_options {
+        let on_off = |on: bool| if on { "on" } else { "off" };
+        Line::from(format!(
+            "Clean: [s]uccessful: {} | [g]roup: {} | Enter: clean | R: reset daemon | Esc: cancel",
+            on_off(options.successful_only),
+            options.group.as_deref().unwrap_or("off"),
+        ))
     } else if ui_state.sort_mode {
         // Build sort options with highlighting for currently selected field
         let highlight = Style::default()
@@ -456,6 +1487,8 @@ pub fn draw(f: &mut Frame, ui_state: &mut UiState) {
         ])
     } else if ui_state.input_mode {
         Line::from(format!("Filter: {}_ (Esc to clear)", ui_state.filter_text))
+    } else if let Some(message) = ui_state.status_message {
+        Line::from(message)
     } else if !ui_state.filter_text.is_empty() {
         Line::from(format!("Filter: {} (Esc to clear)", ui_state.filter_text))
     } else if !ui_state.selected_task_ids.is_empty() {
@@ -463,6 +1496,24 @@ pub fn draw(f: &mut Frame, ui_state: &mut UiState) {
             "{} selected (Esc to clear)",
             ui_state.selected_task_ids.len()
         ))
+    } else if ui_state.daemons.len() > 1 {
+        let mut spans = Vec::new();
+        for (i, daemon) in ui_state.daemons.iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::raw(" | "));
+            }
+            spans.push(match &daemon.error {
+                None => Span::styled(
+                    format!("{}: connected", daemon.name),
+                    Style::default().fg(Color::Green),
+                ),
+                Some(error) => Span::styled(
+                    format!("{}: {}", daemon.name, error),
+                    Style::default().fg(Color::Red),
+                ),
+            });
+        }
+        Line::from(spans)
     } else {
         Line::from("Connected to Pueue daemon")
     };
@@ -495,36 +1546,56 @@ pub fn draw(f: &mut Frame, ui_state: &mut UiState) {
             .scroll((ui_state.help_scroll_offset, 0));
         f.render_widget(help_block, area);
 
-        if line_count > content_height {
-            let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
-                .begin_symbol(Some("↑"))
-                .end_symbol(Some("↓"));
-            let line_count = line_count as usize;
-            let max_offset = line_count.saturating_sub(content_height as usize);
-            // Map scroll offset to full content range so the thumb reaches both ends.
-            // This is needed because help_scroll_offset is a top-of-viewport index,
-            // while the scrollbar thumb position expects a position within the full content range.
-            // Passing the raw offset means the thumb never reaches the bottom.
-            // So we map offset from [0..max_offset] to [0..line_count-1]
-            // The task table doesn't need this because it uses the selected row index.
-            let position = if max_offset == 0 || line_count <= 1 {
-                0
-            } else {
-                let scaled = (ui_state.help_scroll_offset as usize)
-                    .saturating_mul(line_count.saturating_sub(1))
-                    / max_offset;
-                scaled.min(line_count.saturating_sub(1))
-            };
-            let mut scrollbar_state = ScrollbarState::new(line_count)
-                .viewport_content_length(content_height as usize)
-                .position(position);
-            // rnender scrollbar within the help modal border
-            let scrollbar_area = area.inner(Margin {
-                vertical: 1,
-                horizontal: 1,
-            });
-            f.render_stateful_widget(scrollbar, scrollbar_area, &mut scrollbar_state);
-        }
+        render_modal_scrollbar(
+            f,
+            area,
+            line_count,
+            content_height,
+            ui_state.help_scroll_offset,
+        );
+    }
+
+    // Reset confirmation modal
+    if ui_state.confirm_reset {
+        let area = centered_rect(60, 20, f.area());
+        f.render_widget(Clear, area);
+
+        let task_count = ui_state.state.as_ref().map_or(0, |s| s.tasks.len());
+        // Every daemon monitored is reset
+        let (question, title) = match ui_state.daemons {
+            [] | [_] => (
+                format!(
+                    "Kill and remove all {} task(s), and reset the daemon?",
+                    task_count
+                ),
+                " Reset daemon ",
+            ),
+            daemons => {
+                let names: Vec<&str> = daemons.iter().map(|d| d.name.as_str()).collect();
+                (
+                    format!(
+                        "Kill and remove all {} task(s) on {}, and reset these {} daemons?",
+                        task_count,
+                        names.join(", "),
+                        names.len()
+                    ),
+                    " Reset daemons ",
+                )
+            }
+        };
+        let confirm_block = Paragraph::new(format!(
+            "{}\n\nPress y to confirm, any other key to cancel.",
+            question
+        ))
+        .style(Style::default().fg(Color::Yellow))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .border_style(Style::default().fg(Color::Yellow)),
+        )
+        .wrap(Wrap { trim: false });
+        f.render_widget(confirm_block, area);
     }
 
     // Error modal (takes priority over everything else)